@@ -0,0 +1,79 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::environment::{color_to_vec3, vec3_to_color};
+use crate::random::Rng;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Término ambiental modulado por cuántos rayos del hemisferio escapan
+// sin chocar dentro de `max_distance`
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub max_distance: f32,
+    pub ambient: Color,
+    pub intensity: f32,
+}
+
+impl AmbientOcclusion {
+    pub fn new(samples: u32, max_distance: f32, ambient: Color, intensity: f32) -> Self {
+        Self {
+            samples,
+            max_distance,
+            ambient,
+            intensity,
+        }
+    }
+
+    // Fracción del hemisferio visible: 1.0 sin oclusión, 0.0 totalmente tapado
+    pub fn visibility(
+        &self,
+        intersect: &Intersect,
+        objects: &[Box<dyn RayIntersect>],
+        time: f32,
+        rng: &mut Rng,
+    ) -> f32 {
+        let samples = self.samples.max(1);
+        let origin = intersect.offset_origin(&intersect.normal);
+        let mut unoccluded = 0;
+
+        for _ in 0..samples {
+            let direction = cosine_hemisphere(&intersect.normal, rng);
+            let blocked = objects.iter().any(|object| {
+                let hit = object.ray_intersect_at(&origin, &direction, time);
+                hit.is_intersecting && hit.distance > 0.0 && hit.distance < self.max_distance
+            });
+            if !blocked {
+                unoccluded += 1;
+            }
+        }
+
+        unoccluded as f32 / samples as f32
+    }
+
+    // Luz ambiental que recibe la superficie, ya atenuada por la oclusión
+    pub fn shade(
+        &self,
+        intersect: &Intersect,
+        objects: &[Box<dyn RayIntersect>],
+        time: f32,
+        rng: &mut Rng,
+    ) -> Color {
+        let visibility = self.visibility(intersect, objects, time, rng);
+        let ambient = color_to_vec3(intersect.material.diffuse).component_mul(&color_to_vec3(self.ambient))
+            * intersect.material.albedo[0]
+            * self.intensity
+            * visibility;
+        vec3_to_color(&ambient)
+    }
+}
+
+// Dirección con distribución coseno alrededor de la normal
+fn cosine_hemisphere(normal: &Vec3, rng: &mut Rng) -> Vec3 {
+    let (x, y) = rng.in_unit_disk();
+    let z = (1.0 - x * x - y * y).max(0.0).sqrt();
+
+    let helper = if normal.x.abs() > 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+
+    (tangent * x + bitangent * y + normal * z).normalize()
+}
@@ -0,0 +1,279 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use std::rc::Rc;
+use crate::camera::Camera;
+use crate::environment::vec3_to_color;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Linear,
+    // Curva Bézier cúbica con tangentes automáticas (estilo Catmull-Rom)
+    Bezier,
+}
+
+// Valores que se pueden interpolar entre fotogramas clave
+pub trait Animatable: Copy {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+    fn add_scaled(self, other: Self, factor: f32) -> Self;
+}
+
+impl Animatable for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    fn add_scaled(self, other: Self, factor: f32) -> Self {
+        self + other * factor
+    }
+}
+
+impl Animatable for Vec3 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+
+    fn add_scaled(self, other: Self, factor: f32) -> Self {
+        self + other * factor
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Keyframe<T: Animatable> {
+    pub time: f32,
+    pub value: T,
+    // Interpolación del tramo que empieza en este fotograma
+    pub interpolation: Interpolation,
+}
+
+#[derive(Debug, Clone)]
+pub struct Track<T: Animatable> {
+    pub keys: Vec<Keyframe<T>>,
+}
+
+impl<T: Animatable> Track<T> {
+    pub fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    pub fn key(mut self, time: f32, value: T, interpolation: Interpolation) -> Self {
+        self.keys.push(Keyframe { time, value, interpolation });
+        self.keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        self
+    }
+
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+
+        let i = self.keys.windows(2).position(|pair| time >= pair[0].time && time <= pair[1].time)?;
+        let k0 = &self.keys[i];
+        let k1 = &self.keys[i + 1];
+        let span = k1.time - k0.time;
+        let t = if span > 0.0 { (time - k0.time) / span } else { 0.0 };
+
+        match k0.interpolation {
+            Interpolation::Linear => Some(T::lerp(k0.value, k1.value, t)),
+            Interpolation::Bezier => {
+                // Los vecinos definen las tangentes; en los extremos se repite el punto
+                let prev = if i > 0 { self.keys[i - 1].value } else { k0.value };
+                let next = if i + 2 < self.keys.len() { self.keys[i + 2].value } else { k1.value };
+
+                let c1 = k0.value.add_scaled(k1.value, 1.0 / 6.0).add_scaled(prev, -1.0 / 6.0);
+                let c2 = k1.value.add_scaled(next, -1.0 / 6.0).add_scaled(k0.value, 1.0 / 6.0);
+
+                // De Casteljau
+                let a = T::lerp(k0.value, c1, t);
+                let b = T::lerp(c1, c2, t);
+                let c = T::lerp(c2, k1.value, t);
+                let d = T::lerp(a, b, t);
+                let e = T::lerp(b, c, t);
+                Some(T::lerp(d, e, t))
+            }
+        }
+    }
+}
+
+impl<T: Animatable> Default for Track<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CameraAnimation {
+    pub eye: Track<Vec3>,
+    pub center: Track<Vec3>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightAnimation {
+    pub position: Track<Vec3>,
+    // Color en punto flotante (0 a 1 por canal): los puntos de control de la
+    // curva pueden salirse del rango y solo se recorta el resultado
+    pub color: Track<Vec3>,
+    pub intensity: Track<f32>,
+}
+
+// Objeto animado: la geometría se define en su espacio local y en cada
+// instante se coloca con escala, rotación y traslación
+#[derive(Clone)]
+pub struct ObjectAnimation {
+    pub object: Rc<dyn RayIntersect>,
+    pub position: Track<Vec3>,
+    // Ángulos en radianes alrededor de x, y y z, aplicados en ese orden
+    pub rotation: Track<Vec3>,
+    pub scale: Track<Vec3>,
+}
+
+impl ObjectAnimation {
+    pub fn new(object: Rc<dyn RayIntersect>) -> Self {
+        Self {
+            object,
+            position: Track::new(),
+            rotation: Track::new(),
+            scale: Track::new(),
+        }
+    }
+
+    // Las pistas vacías dejan la geometría sin mover, sin girar o sin escalar
+    pub fn transform_at(&self, time: f32) -> Transform {
+        let position = self.position.sample(time).unwrap_or_else(Vec3::zeros);
+        let rotation = self.rotation.sample(time).unwrap_or_else(Vec3::zeros);
+        let scale = self.scale.sample(time).unwrap_or_else(|| Vec3::repeat(1.0));
+
+        Transform::identity(self.object.clone())
+            .scale(scale)
+            .rotate(rotation.x, Vec3::x())
+            .rotate(rotation.y, Vec3::y())
+            .rotate(rotation.z, Vec3::z())
+            .translate(position)
+    }
+}
+
+pub struct Timeline {
+    pub duration: f32,
+    pub fps: f32,
+    pub camera: CameraAnimation,
+    // Animaciones indexadas por la posición de la luz en la escena
+    pub lights: Vec<(usize, LightAnimation)>,
+    // Animaciones indexadas por la posición del objeto en la escena; en cada
+    // instante el objeto se reemplaza por su geometría transformada
+    pub objects: Vec<(usize, ObjectAnimation)>,
+}
+
+impl Timeline {
+    pub fn new(duration: f32, fps: f32) -> Self {
+        Self {
+            duration,
+            fps,
+            camera: CameraAnimation::default(),
+            lights: Vec::new(),
+            objects: Vec::new(),
+        }
+    }
+
+    // Vuelta completa de la cámara alrededor de `center`, ideal para videos de presentación
+    pub fn turntable(duration: f32, fps: f32, center: Vec3, radius: f32, height: f32) -> Self {
+        let mut timeline = Self::new(duration, fps);
+        let steps = 8;
+
+        for i in 0..=steps {
+            let s = i as f32 / steps as f32;
+            let angle = PI / 2.0 + 2.0 * PI * s;
+            let eye = center + Vec3::new(radius * angle.cos(), height, radius * angle.sin());
+            timeline.camera.eye = timeline.camera.eye.key(duration * s, eye, Interpolation::Bezier);
+        }
+        timeline.camera.center = timeline.camera.center.key(0.0, center, Interpolation::Linear);
+
+        timeline
+    }
+
+    pub fn frame_count(&self) -> usize {
+        (self.duration * self.fps).ceil().max(1.0) as usize
+    }
+
+    pub fn frame_time(&self, frame: usize) -> f32 {
+        frame as f32 / self.fps
+    }
+
+    pub fn apply(&self, time: f32, camera: &mut Camera, lights: &mut [Light], objects: &mut [Box<dyn RayIntersect>]) {
+        if let Some(eye) = self.camera.eye.sample(time) {
+            camera.eye = eye;
+        }
+        if let Some(center) = self.camera.center.sample(time) {
+            camera.center = center;
+        }
+
+        for (index, animation) in self.lights.iter() {
+            if let Some(light) = lights.get_mut(*index) {
+                if let Some(position) = animation.position.sample(time) {
+                    light.position = position;
+                }
+                if let Some(color) = animation.color.sample(time) {
+                    light.color = vec3_to_color(&color);
+                }
+                if let Some(intensity) = animation.intensity.sample(time) {
+                    light.intensity = intensity;
+                }
+            }
+        }
+
+        for (index, animation) in self.objects.iter() {
+            if let Some(object) = objects.get_mut(*index) {
+                *object = Box::new(animation.transform_at(time));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_color_track_stays_constant() {
+        // Un color casi blanco no debe saturar los puntos de control de la curva
+        let color = Vec3::repeat(250.0 / 255.0);
+        let track = Track::new()
+            .key(0.0, color, Interpolation::Bezier)
+            .key(1.0, color, Interpolation::Bezier)
+            .key(2.0, color, Interpolation::Linear);
+        for step in 0..=20 {
+            let value = track.sample(step as f32 * 0.1).unwrap();
+            assert!((value - color).norm() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn linear_track_interpolates_and_clamps_to_the_ends() {
+        let track = Track::new().key(1.0, 2.0, Interpolation::Linear).key(3.0, 6.0, Interpolation::Linear);
+        assert_eq!(track.sample(0.0), Some(2.0));
+        assert_eq!(track.sample(2.0), Some(4.0));
+        assert_eq!(track.sample(2.5), Some(5.0));
+        assert_eq!(track.sample(4.0), Some(6.0));
+        assert_eq!(Track::<f32>::new().sample(0.0), None);
+    }
+
+    #[test]
+    fn bezier_track_passes_through_keys_and_eases_between_them() {
+        let track = Track::new()
+            .key(0.0, 0.0, Interpolation::Bezier)
+            .key(1.0, 1.0, Interpolation::Bezier)
+            .key(2.0, 0.0, Interpolation::Bezier);
+        assert!((track.sample(1.0).unwrap() - 1.0).abs() < 1e-6);
+        // Puntos de control 0, 1/6, 1 y 1: la curva sube rápido hacia el máximo
+        assert!((track.sample(0.5).unwrap() - 0.5625).abs() < 1e-6);
+        let early = track.sample(0.25).unwrap();
+        assert!(early > 0.0 && early < 0.5);
+    }
+}
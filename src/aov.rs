@@ -0,0 +1,141 @@
+use nalgebra_glm::Vec3;
+
+// Pasadas auxiliares (AOV) que se guardan junto a la imagen final para componer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pass {
+    Depth,
+    Normal,
+    Albedo,
+    ObjectId,
+    MaterialId,
+    Diffuse,
+    Specular,
+    Reflection,
+    Refraction,
+    Shadow,
+}
+
+impl Pass {
+    pub const ALL: [Pass; 10] = [
+        Pass::Depth,
+        Pass::Normal,
+        Pass::Albedo,
+        Pass::ObjectId,
+        Pass::MaterialId,
+        Pass::Diffuse,
+        Pass::Specular,
+        Pass::Reflection,
+        Pass::Refraction,
+        Pass::Shadow,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pass::Depth => "depth",
+            Pass::Normal => "normal",
+            Pass::Albedo => "albedo",
+            Pass::ObjectId => "object_id",
+            Pass::MaterialId => "material_id",
+            Pass::Diffuse => "diffuse",
+            Pass::Specular => "specular",
+            Pass::Reflection => "reflection",
+            Pass::Refraction => "refraction",
+            Pass::Shadow => "shadow",
+        }
+    }
+}
+
+// Valores de las pasadas en el primer impacto de un rayo de cámara
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AovSample {
+    pub depth: f32,
+    pub normal: Vec3,
+    pub albedo: Vec3,
+    pub object_id: Option<usize>,
+    pub material_id: Option<u32>,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub reflection: Vec3,
+    pub refraction: Vec3,
+    pub shadow: Vec3,
+}
+
+impl AovSample {
+    fn value(&self, pass: Pass) -> Vec3 {
+        match pass {
+            Pass::Depth => Vec3::repeat(self.depth),
+            Pass::Normal => self.normal,
+            Pass::Albedo => self.albedo,
+            Pass::ObjectId => self.object_id.map_or(Vec3::zeros(), |id| id_color(id as u32)),
+            Pass::MaterialId => self.material_id.map_or(Vec3::zeros(), id_color),
+            Pass::Diffuse => self.diffuse,
+            Pass::Specular => self.specular,
+            Pass::Reflection => self.reflection,
+            Pass::Refraction => self.refraction,
+            Pass::Shadow => self.shadow,
+        }
+    }
+}
+
+// Color distinto para cada identificador, para separar objetos al componer
+fn id_color(id: u32) -> Vec3 {
+    let mut h = id.wrapping_add(1).wrapping_mul(0x9E37_79B9);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85EB_CA6B);
+    h ^= h >> 13;
+    Vec3::new((h & 0xFF) as f32, ((h >> 8) & 0xFF) as f32, ((h >> 16) & 0xFF) as f32) / 255.0
+}
+
+// Un búfer en punto flotante por pasada; las muestras de cada píxel se promedian
+// salvo en las pasadas de identificadores, que guardan la primera muestra para
+// que los bordes no mezclen dos colores en uno que no es de ningún objeto
+pub struct AovBuffers {
+    pub width: usize,
+    pub height: usize,
+    layers: Vec<Vec<Vec3>>,
+    has_id: Vec<bool>,
+}
+
+impl AovBuffers {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            layers: vec![vec![Vec3::zeros(); width * height]; Pass::ALL.len()],
+            has_id: vec![false; width * height],
+        }
+    }
+
+    pub fn accumulate(&mut self, x: usize, y: usize, sample: &AovSample, weight: f32) {
+        let index = y * self.width + x;
+        let first = !self.has_id[index];
+        self.has_id[index] = true;
+        for pass in Pass::ALL {
+            match pass {
+                Pass::ObjectId | Pass::MaterialId => {
+                    if first {
+                        self.layers[pass as usize][index] = sample.value(pass);
+                    }
+                }
+                _ => self.layers[pass as usize][index] += sample.value(pass) * weight,
+            }
+        }
+    }
+
+    pub fn layer(&self, pass: Pass) -> &[Vec3] {
+        &self.layers[pass as usize]
+    }
+
+    // Cada pasada se guarda como una imagen EXR en punto flotante `<prefijo>_<pasada>.exr`
+    pub fn save_exr(&self, prefix: &str) -> image::ImageResult<()> {
+        for pass in Pass::ALL {
+            let layer = self.layer(pass);
+            let image = image::Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+                let value = layer[y as usize * self.width + x as usize];
+                image::Rgb([value.x, value.y, value.z])
+            });
+            image.save(format!("{}_{}.exr", prefix, pass.name()))?;
+        }
+        Ok(())
+    }
+}
@@ -1,14 +1,83 @@
-use nalgebra_glm::{Vec3, dot};
-use crate::ray_intersect::{RayIntersect, Intersect};
+use nalgebra_glm::{Vec2, Vec3};
+use crate::ray_intersect::{RayIntersect, Intersect, Interval};
 use crate::material::Material;
+// Caras del bloque, en el orden en que se guardan `faces` y `atlas`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Face {
+    Left,
+    Right,
+    Bottom,
+    Top,
+    Back,
+    Front,
+}
+
+impl Face {
+    fn from_normal(normal: &Vec3) -> Self {
+        if normal.x != 0.0 {
+            if normal.x < 0.0 { Face::Left } else { Face::Right }
+        } else if normal.y != 0.0 {
+            if normal.y < 0.0 { Face::Bottom } else { Face::Top }
+        } else if normal.z < 0.0 {
+            Face::Back
+        } else {
+            Face::Front
+        }
+    }
+}
+
+// Rectángulo de un atlas de texturas en coordenadas UV
+#[derive(Debug, Clone, Copy)]
+pub struct UvRect {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl UvRect {
+    pub fn new(min: Vec2, max: Vec2) -> Self {
+        Self { min, max }
+    }
+
+    fn map(&self, uv: &Vec2) -> Vec2 {
+        self.min + (self.max - self.min).component_mul(uv)
+    }
+}
+
 pub struct Block {
     pub min: Vec3,
     pub max: Vec3,
     pub material: Material,
+    // Material propio de cada cara, indexado por `Face`
+    pub faces: Option<[Material; 6]>,
+    // Región del atlas que usa cada cara, indexada por `Face`
+    pub atlas: Option<[UvRect; 6]>,
 }
 
-impl RayIntersect for Block {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+impl Block {
+    pub fn new(min: Vec3, max: Vec3, material: Material) -> Self {
+        Self {
+            min,
+            max,
+            material,
+            faces: None,
+            atlas: None,
+        }
+    }
+
+    // Materiales distintos arriba, a los lados y abajo (p. ej. tronco o césped)
+    pub fn with_faces(mut self, top: Material, side: Material, bottom: Material) -> Self {
+        self.faces = Some([side, side, bottom, top, side, side]);
+        self
+    }
+
+    // Cada cara toma su región del atlas de la textura del material
+    pub fn with_atlas(mut self, top: UvRect, side: UvRect, bottom: UvRect) -> Self {
+        self.atlas = Some([side, side, bottom, top, side, side]);
+        self
+    }
+
+    // Distancias de entrada y salida del rayo junto con las normales de cada cara
+    fn slab_hits(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, Vec3, f32, Vec3)> {
         let inv_dir = Vec3::new(
             1.0 / ray_direction.x,
             1.0 / ray_direction.y,
@@ -37,17 +106,100 @@ impl RayIntersect for Block {
         let t2 = t_max.x.min(t_max.y).min(t_max.z);
 
         if t1 <= t2 && t2 > 0.0 {
-            let point = ray_origin + ray_direction * t1;
-            let normal = if point.x == self.min.x { Vec3::new(-1.0, 0.0, 0.0) }
-                        else if point.x == self.max.x { Vec3::new(1.0, 0.0, 0.0) }
-                        else if point.y == self.min.y { Vec3::new(0.0, -1.0, 0.0) }
-                        else if point.y == self.max.y { Vec3::new(0.0, 1.0, 0.0) }
-                        else if point.z == self.min.z { Vec3::new(0.0, 0.0, -1.0) }
-                        else { Vec3::new(0.0, 0.0, 1.0) };
-
-            Intersect::new(point, normal, t1, self.material)
+            // La cara de entrada es la del eje con el mayor t_min y la de salida
+            // la del eje con el menor t_max
+            let enter_axis = if t1 == t_min.x { 0 } else if t1 == t_min.y { 1 } else { 2 };
+            let exit_axis = if t2 == t_max.x { 0 } else if t2 == t_max.y { 1 } else { 2 };
+
+            let mut enter_normal = Vec3::zeros();
+            enter_normal[enter_axis] = -ray_direction[enter_axis].signum();
+            let mut exit_normal = Vec3::zeros();
+            exit_normal[exit_axis] = ray_direction[exit_axis].signum();
+
+            Some((t1, enter_normal, t2, exit_normal))
         } else {
-            Intersect::empty()
+            None
+        }
+    }
+
+    // Coordenadas UV de un punto sobre la cara con la normal dada, junto con
+    // las direcciones en que crecen u y v y el tamaño de la cara en cada una
+    fn uv_at(&self, point: &Vec3, normal: &Vec3) -> (Vec2, Vec3, Vec3, Vec2) {
+        let size = self.max - self.min;
+        let local = (point - self.min).component_div(&size);
+        if normal.x != 0.0 {
+            (Vec2::new(local.z, 1.0 - local.y), Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, -1.0, 0.0), Vec2::new(size.z, size.y))
+        } else if normal.y != 0.0 {
+            (Vec2::new(local.x, local.z), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0), Vec2::new(size.x, size.z))
+        } else {
+            (Vec2::new(local.x, 1.0 - local.y), Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0), Vec2::new(size.x, size.y))
+        }
+    }
+
+    fn face_hit(&self, ray_origin: &Vec3, ray_direction: &Vec3, t: f32, normal: Vec3) -> Intersect {
+        let point = ray_origin + ray_direction * t;
+        let face = Face::from_normal(&normal);
+        let (mut uv, tangent, bitangent, mut uv_scale) = self.uv_at(&point, &normal);
+        if let Some(atlas) = &self.atlas {
+            let rect = atlas[face as usize];
+            uv = rect.map(&uv);
+            uv_scale = uv_scale.component_div(&(rect.max - rect.min));
+        }
+        let material = self.faces.map_or(self.material, |faces| faces[face as usize]);
+
+        Intersect::new(point, normal, t, material)
+            .with_uv(uv)
+            .with_tangent_frame(tangent, bitangent)
+            .with_uv_scale(uv_scale)
+            .with_face(face)
+    }
+}
+
+impl RayIntersect for Block {
+    fn name(&self) -> String {
+        format!(
+            "Bloque ({}, {}, {})-({}, {}, {})",
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z
+        )
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        let (t1, enter_normal, t2, exit_normal) = match self.slab_hits(ray_origin, ray_direction) {
+            Some(hits) => hits,
+            None => return Intersect::empty(),
+        };
+
+        // Si el rayo nace dentro del bloque, la primera cara visible es la de salida
+        if t1 < 0.0 {
+            let exit = self.face_hit(ray_origin, ray_direction, t2, exit_normal);
+            return if exit.material.is_cutout(&exit.uv) { Intersect::empty() } else { exit };
+        }
+
+        let front = self.face_hit(ray_origin, ray_direction, t1, enter_normal);
+        if !front.material.is_cutout(&front.uv) {
+            return front;
+        }
+
+        // Por un hueco de la textura se ve la cara trasera desde adentro
+        let mut back = self.face_hit(ray_origin, ray_direction, t2, exit_normal);
+        back.flip_normal();
+        if back.material.is_cutout(&back.uv) { Intersect::empty() } else { back }
+    }
+
+    // Un sólido no puede quedar abierto por un solo lado: si el rayo cruza un
+    // hueco de la textura en la cara de entrada o en la de salida, en las
+    // operaciones CSG el bloque se trata como vacío a lo largo de ese rayo
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, _time: f32) -> Vec<Interval> {
+        let (t1, enter_normal, t2, exit_normal) = match self.slab_hits(ray_origin, ray_direction) {
+            Some(hits) => hits,
+            None => return vec![],
+        };
+
+        let enter = self.face_hit(ray_origin, ray_direction, t1, enter_normal);
+        let exit = self.face_hit(ray_origin, ray_direction, t2, exit_normal);
+        if enter.material.is_cutout(&enter.uv) || exit.material.is_cutout(&exit.uv) {
+            return vec![];
         }
+        vec![Interval { enter, exit }]
     }
 }
@@ -1,10 +1,23 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
+use crate::random::Rng;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bokeh {
+    Disk,
+    Polygon { blades: u32, rotation: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
     pub eye: Vec3,
     pub center: Vec3,
-    pub up: Vec3
+    pub up: Vec3,
+    pub aperture: f32,
+    pub focus_distance: f32,
+    pub bokeh: Bokeh,
+    pub shutter_open: f32,
+    pub shutter_close: f32,
 }
 
 impl Camera {
@@ -12,7 +25,12 @@ impl Camera {
         Camera {
             eye,
             center,
-            up
+            up,
+            aperture: 0.0,
+            focus_distance: (center - eye).magnitude(),
+            bokeh: Bokeh::Disk,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
         }
     }
 
@@ -23,7 +41,48 @@ impl Camera {
 
         let rotated = vector.x * right + vector.y * up - vector.z * forward;
 
-        return rotated.normalize();
+        rotated.normalize()
+    }
+
+    // Genera el rayo primario para una dirección en espacio de cámara.
+    // Con apertura cero se comporta como una cámara estenopeica.
+    pub fn primary_ray(&self, camera_direction: &Vec3, rng: &mut Rng) -> (Vec3, Vec3) {
+        let direction = self.base_change(camera_direction);
+
+        if self.aperture <= 0.0 {
+            return (self.eye, direction);
+        }
+
+        // El plano de enfoque es perpendicular al eje de la cámara
+        let cos_theta = (-camera_direction.z / camera_direction.magnitude()).max(1e-4);
+        let focus_point = self.eye + direction * (self.focus_distance / cos_theta);
+
+        let (lens_x, lens_y) = match self.bokeh {
+            Bokeh::Disk => rng.in_unit_disk(),
+            Bokeh::Polygon { blades, rotation } => rng.in_regular_polygon(blades, rotation),
+        };
+
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+
+        let origin = self.eye + (right * lens_x + up * lens_y) * self.aperture;
+        (origin, (focus_point - origin).normalize())
+    }
+
+    // Instante aleatorio entre la apertura y el cierre del obturador
+    pub fn sample_time(&self, rng: &mut Rng) -> f32 {
+        if self.shutter_close <= self.shutter_open {
+            return self.shutter_open;
+        }
+        self.shutter_open + (self.shutter_close - self.shutter_open) * rng.next_f32()
+    }
+
+    // Ajusta la distancia de enfoque a partir de un impacto visto a lo largo
+    // de una dirección en espacio de cámara.
+    pub fn focus_on(&mut self, camera_direction: &Vec3, hit_distance: f32) {
+        let cos_theta = -camera_direction.z / camera_direction.magnitude();
+        self.focus_distance = (hit_distance * cos_theta).max(1e-3);
     }
 
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
@@ -45,7 +104,7 @@ impl Camera {
 
         self.eye = new_eye;
     }
-    
+
     pub fn zoom(&mut self, delta: f32) {
         // Calcula la dirección del vector desde la cámara al centro
         let direction = (self.center - self.eye).normalize();
@@ -0,0 +1,119 @@
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{RayIntersect, Intersect, Interval};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    // El objeto izquierdo menos el derecho
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(&self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+// Nodo de geometría sólida constructiva sobre dos objetos cualesquiera
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<dyn RayIntersect>,
+    pub right: Box<dyn RayIntersect>,
+}
+
+impl Csg {
+    pub fn new(operation: CsgOperation, left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Self { operation, left, right }
+    }
+
+    pub fn union(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference(left: Box<dyn RayIntersect>, right: Box<dyn RayIntersect>) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+}
+
+struct Event {
+    intersect: Intersect,
+    from_left: bool,
+    entering: bool,
+}
+
+impl RayIntersect for Csg {
+    fn name(&self) -> String {
+        format!("CSG {:?} de {} y {}", self.operation, self.left.name(), self.right.name())
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect {
+        // Primer borde del resultado que queda delante del origen del rayo
+        for interval in self.ray_intervals(ray_origin, ray_direction, time) {
+            if interval.enter.distance > 0.0 {
+                return interval.enter;
+            }
+            if interval.exit.distance > 0.0 {
+                return interval.exit;
+            }
+        }
+        Intersect::empty()
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Interval> {
+        let mut events = Vec::new();
+        for (from_left, object) in [(true, &self.left), (false, &self.right)] {
+            for interval in object.ray_intervals(ray_origin, ray_direction, time) {
+                events.push(Event { intersect: interval.enter, from_left, entering: true });
+                events.push(Event { intersect: interval.exit, from_left, entering: false });
+            }
+        }
+        events.sort_by(|a, b| a.intersect.distance.total_cmp(&b.intersect.distance));
+
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut inside = false;
+        let mut enter = Intersect::empty();
+        let mut intervals = Vec::new();
+
+        for event in events {
+            if event.from_left {
+                in_left = event.entering;
+            } else {
+                in_right = event.entering;
+            }
+
+            let now_inside = self.operation.contains(in_left, in_right);
+            if now_inside == inside {
+                continue;
+            }
+
+            let mut boundary = event.intersect;
+            // En la diferencia, las caras del objeto restado quedan invertidas
+            if self.operation == CsgOperation::Difference && !event.from_left {
+                boundary.flip_normal();
+            }
+
+            if now_inside {
+                enter = boundary;
+            } else {
+                intervals.push(Interval { enter, exit: boundary });
+            }
+            inside = now_inside;
+        }
+
+        intervals
+    }
+}
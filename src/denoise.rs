@@ -0,0 +1,99 @@
+use nalgebra_glm::Vec3;
+use crate::aov::{AovBuffers, Pass};
+use crate::framebuffer::{pack_color, unpack_color, Framebuffer};
+
+// Núcleo B3-spline de 5 muestras por eje del filtro à-trous
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+// Filtro à-trous guiado por las pasadas de normal, albedo y profundidad:
+// promedia vecinos cada vez más lejanos pero no cruza bordes geométricos
+pub struct Denoiser {
+    pub iterations: u32,
+    // Qué tanto pueden diferir los colores de dos vecinos antes de ignorarse
+    pub color_sigma: f32,
+    // Exponente sobre el coseno entre normales; más alto preserva más bordes
+    pub normal_power: f32,
+    pub depth_sigma: f32,
+}
+
+impl Denoiser {
+    pub fn new(iterations: u32, color_sigma: f32, normal_power: f32, depth_sigma: f32) -> Self {
+        Self {
+            iterations,
+            color_sigma,
+            normal_power,
+            depth_sigma,
+        }
+    }
+
+    // Filtra la imagen del framebuffer y deja el resultado en el búfer de color.
+    // Si hay muestras acumuladas se filtra su promedio en punto flotante, que
+    // queda intacto para las pasadas siguientes. El color se divide por el
+    // albedo antes de filtrar para no desenfocar los detalles de las texturas.
+    pub fn apply(&self, framebuffer: &mut Framebuffer, aovs: &AovBuffers) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let albedo = aovs.layer(Pass::Albedo);
+        let normals = aovs.layer(Pass::Normal);
+        let depth = aovs.layer(Pass::Depth);
+
+        let demodulate = |index: usize| albedo[index].map(|c| c.max(1e-3));
+        let samples = framebuffer.accumulated_samples();
+        let source: Vec<Vec3> = if samples > 0 {
+            framebuffer.accumulation.iter().map(|sum| sum / samples as f32).collect()
+        } else {
+            framebuffer.buffer.iter().map(|&pixel| unpack_color(pixel)).collect()
+        };
+        let mut color: Vec<Vec3> = source
+            .iter()
+            .enumerate()
+            .map(|(index, value)| value.component_div(&demodulate(index)))
+            .collect();
+        let mut filtered = color.clone();
+
+        for iteration in 0..self.iterations {
+            let step = 1i64 << iteration;
+            // Cada pasada tolera menos diferencia de color que la anterior
+            let color_sigma = self.color_sigma / (1u32 << iteration) as f32;
+
+            for y in 0..height {
+                for x in 0..width {
+                    let center = y * width + x;
+                    let mut sum = color[center] * KERNEL[2] * KERNEL[2];
+                    let mut weight_sum = KERNEL[2] * KERNEL[2];
+
+                    for (j, ky) in KERNEL.iter().enumerate() {
+                        for (i, kx) in KERNEL.iter().enumerate() {
+                            if i == 2 && j == 2 {
+                                continue;
+                            }
+                            let qx = x as i64 + (i as i64 - 2) * step;
+                            let qy = y as i64 + (j as i64 - 2) * step;
+                            if qx < 0 || qy < 0 || qx >= width as i64 || qy >= height as i64 {
+                                continue;
+                            }
+                            let neighbor = qy as usize * width + qx as usize;
+
+                            let color_distance = (color[center] - color[neighbor]).norm_squared();
+                            let color_weight = (-color_distance / (color_sigma * color_sigma).max(1e-6)).exp();
+                            let normal_weight = normals[center].dot(&normals[neighbor]).max(0.0).powf(self.normal_power);
+                            let depth_distance = (depth[center].x - depth[neighbor].x).abs();
+                            let depth_weight = (-depth_distance / (self.depth_sigma * step as f32)).exp();
+
+                            let weight = kx * ky * color_weight * normal_weight * depth_weight;
+                            sum += color[neighbor] * weight;
+                            weight_sum += weight;
+                        }
+                    }
+
+                    filtered[center] = sum / weight_sum;
+                }
+            }
+
+            std::mem::swap(&mut color, &mut filtered);
+        }
+
+        for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+            *pixel = pack_color(&color[index].component_mul(&demodulate(index)));
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use nalgebra_glm::{Vec2, Vec3};
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::texture::Texture;
+
+// Lo que ve un rayo que no choca con ningún objeto
+pub enum Environment {
+    Solid(Color),
+    Gradient { zenith: Color, horizon: Color, ground: Color },
+    Sky(PreethamSky),
+    CubeMap(CubeMap),
+    Equirectangular(EquirectangularMap),
+}
+
+impl Environment {
+    // Radiancia lineal en la dirección dada (puede superar 1.0 en mapas HDR)
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let direction = direction.normalize();
+        match self {
+            Environment::Solid(color) => color_to_vec3(*color),
+            Environment::Gradient { zenith, horizon, ground } => {
+                if direction.y >= 0.0 {
+                    let t = direction.y.sqrt();
+                    color_to_vec3(*horizon) * (1.0 - t) + color_to_vec3(*zenith) * t
+                } else {
+                    let t = (-direction.y * 4.0).min(1.0);
+                    color_to_vec3(*horizon) * (1.0 - t) + color_to_vec3(*ground) * t
+                }
+            }
+            Environment::Sky(sky) => sky.radiance(&direction),
+            Environment::CubeMap(cube) => cube.radiance(&direction),
+            Environment::Equirectangular(map) => map.radiance(&direction),
+        }
+    }
+
+    pub fn sample(&self, direction: &Vec3) -> Color {
+        vec3_to_color(&self.radiance(direction))
+    }
+}
+
+pub fn color_to_vec3(color: Color) -> Vec3 {
+    Vec3::new(color.r as f32, color.g as f32, color.b as f32) / 255.0
+}
+
+pub fn vec3_to_color(radiance: &Vec3) -> Color {
+    let channel = |value: f32| (value * 255.0).clamp(0.0, 255.0) as u8;
+    Color::new(channel(radiance.x), channel(radiance.y), channel(radiance.z))
+}
+
+// Modelo analítico de cielo de Preetham, Shirley y Smits (1999)
+pub struct PreethamSky {
+    pub sun_direction: Vec3,
+    pub turbidity: f32,
+    pub exposure: f32,
+    perez_y: [f32; 5],
+    perez_x: [f32; 5],
+    perez_yc: [f32; 5],
+    // Cromaticidad (x, y) en el cenit; la luminancia se normaliza y no se guarda
+    zenith: Vec2,
+}
+
+impl PreethamSky {
+    pub fn new(sun_direction: Vec3, turbidity: f32, exposure: f32) -> Self {
+        let sun_direction = sun_direction.normalize();
+        let t = turbidity;
+
+        let perez_y = [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703];
+        let perez_x = [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452];
+        let perez_yc = [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529];
+
+        // Cromaticidad en el cenit según el ángulo del sol
+        let theta_s = sun_direction.y.clamp(0.0, 1.0).acos();
+        let (t2, th2, th3) = (t * t, theta_s * theta_s, theta_s * theta_s * theta_s);
+
+        let zenith_x = t2 * (0.00166 * th3 - 0.00375 * th2 + 0.00209 * theta_s)
+            + t * (-0.02903 * th3 + 0.06377 * th2 - 0.03202 * theta_s + 0.00394)
+            + (0.11693 * th3 - 0.21196 * th2 + 0.06052 * theta_s + 0.25886);
+        let zenith_y = t2 * (0.00275 * th3 - 0.00610 * th2 + 0.00317 * theta_s)
+            + t * (-0.04214 * th3 + 0.08970 * th2 - 0.04153 * theta_s + 0.00516)
+            + (0.15346 * th3 - 0.26756 * th2 + 0.06670 * theta_s + 0.26688);
+
+        Self {
+            sun_direction,
+            turbidity,
+            exposure,
+            perez_y,
+            perez_x,
+            perez_yc,
+            zenith: Vec2::new(zenith_x, zenith_y),
+        }
+    }
+
+    fn perez(coefficients: &[f32; 5], theta: f32, gamma: f32) -> f32 {
+        let [a, b, c, d, e] = *coefficients;
+        (1.0 + a * (b / theta.cos()).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+    }
+
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        // Por debajo del horizonte se repite el color del horizonte
+        let up = direction.y.max(0.001);
+        let direction = Vec3::new(direction.x, up, direction.z).normalize();
+
+        let theta = direction.y.acos();
+        let theta_s = self.sun_direction.y.clamp(0.0, 1.0).acos();
+        let gamma = direction.dot(&self.sun_direction).clamp(-1.0, 1.0).acos();
+
+        let relative = |coefficients: &[f32; 5], zenith: f32| {
+            zenith * Self::perez(coefficients, theta, gamma) / Self::perez(coefficients, 0.0, theta_s)
+        };
+
+        // La luminancia se normaliza respecto al cenit para mantenerla en un rango visible
+        let luminance = relative(&self.perez_y, 1.0);
+        let x = relative(&self.perez_x, self.zenith.x);
+        let y = relative(&self.perez_yc, self.zenith.y);
+
+        // xyY -> XYZ -> RGB lineal
+        let big_x = x / y * luminance;
+        let big_z = (1.0 - x - y) / y * luminance;
+        let rgb = Vec3::new(
+            3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z,
+            -0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z,
+            0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z,
+        );
+
+        // Disco solar
+        let sun = if gamma < 0.02 { Vec3::new(20.0, 18.0, 15.0) } else { Vec3::zeros() };
+
+        (rgb.map(|c| c.max(0.0)) + sun) * self.exposure
+    }
+}
+
+// Seis imágenes en el orden +x, -x, +y, -y, +z, -z
+pub struct CubeMap {
+    pub faces: Box<[Texture; 6]>,
+}
+
+impl CubeMap {
+    pub fn load(directory: &str) -> image::ImageResult<Self> {
+        let face = |name: &str| Texture::load(&format!("{}/{}.png", directory, name));
+        Ok(Self {
+            faces: Box::new([face("px")?, face("nx")?, face("py")?, face("ny")?, face("pz")?, face("nz")?]),
+        })
+    }
+
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let abs = direction.abs();
+
+        // Cara del eje dominante y coordenadas (u, v) dentro de ella
+        let (face, u, v, major) = if abs.x >= abs.y && abs.x >= abs.z {
+            if direction.x > 0.0 { (0, -direction.z, -direction.y, abs.x) } else { (1, direction.z, -direction.y, abs.x) }
+        } else if abs.y >= abs.z {
+            if direction.y > 0.0 { (2, direction.x, direction.z, abs.y) } else { (3, direction.x, -direction.z, abs.y) }
+        } else if direction.z > 0.0 {
+            (4, direction.x, -direction.y, abs.z)
+        } else {
+            (5, -direction.x, -direction.y, abs.z)
+        };
+
+        let u = 0.5 * (u / major + 1.0);
+        let v = 0.5 * (v / major + 1.0);
+        self.faces[face].sample(u.clamp(0.0, 1.0), v.clamp(0.0, 1.0)).xyz()
+    }
+}
+
+// Mapa de entorno en proyección equirectangular (latitud-longitud), p. ej. un .hdr
+pub struct EquirectangularMap {
+    pub texture: Texture,
+    pub exposure: f32,
+}
+
+impl EquirectangularMap {
+    pub fn load(path: &str, exposure: f32) -> image::ImageResult<Self> {
+        Ok(Self {
+            texture: Texture::load(path)?,
+            exposure,
+        })
+    }
+
+    pub fn direction_to_uv(direction: &Vec3) -> (f32, f32) {
+        let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+        let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+        (u, v)
+    }
+
+    pub fn radiance(&self, direction: &Vec3) -> Vec3 {
+        let (u, v) = Self::direction_to_uv(direction);
+        self.texture.sample(u, v).xyz() * self.exposure
+    }
+}
@@ -1,9 +1,54 @@
+use nalgebra_glm::Vec3;
+
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
     pub buffer: Vec<u32>,
+    // Suma en punto flotante de las muestras de cada píxel
+    pub accumulation: Vec<Vec3>,
+    accumulated_samples: u32,
     background_color: u32,
     current_color: u32,
+    // Opacidad con la que `point` y las figuras mezclan el color actual
+    current_alpha: f32,
+}
+
+// Región rectangular de la imagen, p. ej. un mosaico de render
+#[derive(Debug, Clone, Copy)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Acceso a un mosaico del framebuffer con coordenadas relativas a su esquina
+pub struct FramebufferView<'a> {
+    framebuffer: &'a mut Framebuffer,
+    pub tile: Tile,
+}
+
+impl FramebufferView<'_> {
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
+        if x < self.tile.width && y < self.tile.height {
+            self.framebuffer.set_pixel(self.tile.x + x, self.tile.y + y, color);
+        }
+    }
+
+    pub fn accumulate(&mut self, x: usize, y: usize, color: &Vec3) {
+        if x < self.tile.width && y < self.tile.height {
+            self.framebuffer.accumulate(self.tile.x + x, self.tile.y + y, color);
+        }
+    }
+}
+
+pub fn unpack_color(pixel: u32) -> Vec3 {
+    Vec3::new(((pixel >> 16) & 0xFF) as f32, ((pixel >> 8) & 0xFF) as f32, (pixel & 0xFF) as f32) / 255.0
+}
+
+pub fn pack_color(color: &Vec3) -> u32 {
+    let channel = |value: f32| (value * 255.0).round().clamp(0.0, 255.0) as u32;
+    (channel(color.x) << 16) | (channel(color.y) << 8) | channel(color.z)
 }
 
 impl Framebuffer {
@@ -12,18 +57,32 @@ impl Framebuffer {
             width,
             height,
             buffer: vec![0; width * height],
+            accumulation: vec![Vec3::zeros(); width * height],
+            accumulated_samples: 0,
             background_color: 0x000000,
             current_color: 0xFFFFFF,
+            current_alpha: 1.0,
         }
     }
 
+    // Cambia el tamaño del búfer; el contenido se descarta y queda el color de fondo
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.buffer = vec![self.background_color; width * height];
+        self.accumulation = vec![Vec3::zeros(); width * height];
+        self.accumulated_samples = 0;
+    }
+
     pub fn clear(&mut self) {
         self.buffer.fill(self.background_color);
     }
 
     pub fn point(&mut self, x: usize, y: usize) {
-        if x < self.width && y < self.height {
-            self.buffer[y * self.width + x] = self.current_color;
+        if self.current_alpha >= 1.0 {
+            self.set_pixel(x, y, self.current_color);
+        } else {
+            self.blend_pixel(x, y, self.current_color, self.current_alpha);
         }
     }
 
@@ -34,4 +93,149 @@ impl Framebuffer {
     pub fn set_current_color(&mut self, color: u32) {
         self.current_color = color;
     }
+
+    pub fn set_current_alpha(&mut self, alpha: f32) {
+        self.current_alpha = alpha.clamp(0.0, 1.0);
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: u32) {
+        if x < self.width && y < self.height {
+            self.buffer[y * self.width + x] = color;
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u32> {
+        (x < self.width && y < self.height).then(|| self.buffer[y * self.width + x])
+    }
+
+    // Mezcla el color sobre el píxel actual con la opacidad dada
+    pub fn blend_pixel(&mut self, x: usize, y: usize, color: u32, alpha: f32) {
+        if let Some(below) = self.get_pixel(x, y) {
+            let mixed = unpack_color(below) * (1.0 - alpha) + unpack_color(color) * alpha;
+            self.set_pixel(x, y, pack_color(&mixed));
+        }
+    }
+
+    // Segmento con el algoritmo de Bresenham; los puntos fuera de la imagen se ignoran
+    pub fn line(&mut self, x0: isize, y0: isize, x1: isize, y1: isize) {
+        let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+        let (step_x, step_y) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+        let (mut x, mut y, mut error) = (x0, y0, dx + dy);
+
+        loop {
+            if x >= 0 && y >= 0 {
+                self.point(x as usize, y as usize);
+            }
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    pub fn rect(&mut self, x: isize, y: isize, width: isize, height: isize) {
+        if width <= 0 || height <= 0 {
+            return;
+        }
+        let (right, bottom) = (x + width - 1, y + height - 1);
+        self.line(x, y, right, y);
+        self.line(x, bottom, right, bottom);
+        self.line(x, y + 1, x, bottom - 1);
+        self.line(right, y + 1, right, bottom - 1);
+    }
+
+    pub fn fill_rect(&mut self, x: isize, y: isize, width: isize, height: isize) {
+        for row in y.max(0)..(y + height).min(self.height as isize) {
+            for column in x.max(0)..(x + width).min(self.width as isize) {
+                self.point(column as usize, row as usize);
+            }
+        }
+    }
+
+    pub fn accumulate(&mut self, x: usize, y: usize, color: &Vec3) {
+        if x < self.width && y < self.height {
+            self.accumulation[y * self.width + x] += color;
+        }
+    }
+
+    pub fn clear_accumulation(&mut self) {
+        self.accumulation.fill(Vec3::zeros());
+        self.accumulated_samples = 0;
+    }
+
+    pub fn accumulated_samples(&self) -> u32 {
+        self.accumulated_samples
+    }
+
+    // Cierra una pasada de acumulación y muestra el promedio en el búfer de color
+    pub fn resolve_accumulation(&mut self) {
+        self.accumulated_samples += 1;
+        let n = self.accumulated_samples as f32;
+        for (pixel, sum) in self.buffer.iter_mut().zip(self.accumulation.iter()) {
+            *pixel = pack_color(&(sum / n));
+        }
+    }
+
+    // Divide la imagen en mosaicos de `size` x `size`; los del borde pueden ser menores
+    pub fn tiles(&self, size: usize) -> Vec<Tile> {
+        let size = size.max(1);
+        let mut tiles = Vec::new();
+        for y in (0..self.height).step_by(size) {
+            for x in (0..self.width).step_by(size) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: size.min(self.width - x),
+                    height: size.min(self.height - y),
+                });
+            }
+        }
+        tiles
+    }
+
+    pub fn view(&mut self, tile: Tile) -> FramebufferView<'_> {
+        FramebufferView { framebuffer: self, tile }
+    }
+
+    // Guarda la imagen; en formato EXR se exporta el promedio acumulado en punto flotante
+    pub fn save(&self, path: &str) -> image::ImageResult<()> {
+        if path.ends_with(".exr") {
+            let n = self.accumulated_samples.max(1) as f32;
+            let image = image::Rgb32FImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+                let index = y as usize * self.width + x as usize;
+                let color = if self.accumulated_samples > 0 {
+                    self.accumulation[index] / n
+                } else {
+                    unpack_color(self.buffer[index])
+                };
+                image::Rgb([color.x, color.y, color.z])
+            });
+            return image.save(path);
+        }
+
+        let image = image::RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let pixel = self.buffer[y as usize * self.width + x as usize];
+            image::Rgb([(pixel >> 16) as u8, (pixel >> 8) as u8, pixel as u8])
+        });
+        image.save(path)
+    }
+
+    // Copia la imagen ampliada (vecino más cercano) a un búfer de otro tamaño
+    pub fn upscale_into(&self, target: &mut [u32], width: usize, height: usize) {
+        for y in 0..height {
+            let source_y = y * self.height / height;
+            for x in 0..width {
+                let source_x = x * self.width / width;
+                target[y * width + x] = self.buffer[source_y * self.width + source_x];
+            }
+        }
+    }
 }
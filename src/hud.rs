@@ -0,0 +1,126 @@
+use crate::framebuffer::Framebuffer;
+
+// Fuente de mapa de bits de 5x7 incluida en el programa: cada fila es un byte
+// cuyos 5 bits bajos son los píxeles, el bit 4 a la izquierda
+const GLYPH_WIDTH: isize = 5;
+const GLYPH_HEIGHT: isize = 7;
+const ADVANCE: isize = GLYPH_WIDTH + 1;
+const LINE_HEIGHT: isize = GLYPH_HEIGHT + 3;
+const PADDING: isize = 3;
+
+fn glyph(character: char) -> [u8; 7] {
+    // Las vocales con tilde y la eñe se dibujan como su letra base
+    let character = match character {
+        'á' | 'Á' => 'A',
+        'é' | 'É' => 'E',
+        'í' | 'Í' => 'I',
+        'ó' | 'Ó' => 'O',
+        'ú' | 'Ú' => 'U',
+        'ñ' | 'Ñ' => 'N',
+        other => other.to_ascii_uppercase(),
+    };
+
+    match character {
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        ' ' => [0x00; 7],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+pub fn text_width(text: &str) -> isize {
+    (text.chars().count() as isize * ADVANCE - 1).max(0)
+}
+
+// Escribe el texto con el color actual; (x, y) es la esquina superior izquierda
+pub fn draw_text(framebuffer: &mut Framebuffer, x: isize, y: isize, text: &str) {
+    for (index, character) in text.chars().enumerate() {
+        let left = x + index as isize * ADVANCE;
+        for (row, bits) in glyph(character).iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                let (px, py) = (left + column, y + row as isize);
+                if bits & (0x10 >> column) != 0 && px >= 0 && py >= 0 {
+                    framebuffer.point(px as usize, py as usize);
+                }
+            }
+        }
+    }
+}
+
+// Ancho y alto en píxeles del panel con esas líneas, incluido el margen
+pub fn panel_size(lines: &[String]) -> (isize, isize) {
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0) + 2 * PADDING;
+    let height = lines.len() as isize * LINE_HEIGHT - (LINE_HEIGHT - GLYPH_HEIGHT) + 2 * PADDING;
+    (width, height)
+}
+
+// Varias líneas de texto sobre un fondo oscuro semitransparente
+pub fn draw_panel(framebuffer: &mut Framebuffer, x: isize, y: isize, lines: &[String]) {
+    let (width, height) = panel_size(lines);
+
+    framebuffer.set_current_color(0x000000);
+    framebuffer.set_current_alpha(0.6);
+    framebuffer.fill_rect(x, y, width, height);
+
+    framebuffer.set_current_color(0xFFFFFF);
+    framebuffer.set_current_alpha(1.0);
+    for (index, line) in lines.iter().enumerate() {
+        draw_text(framebuffer, x + PADDING, y + PADDING + index as isize * LINE_HEIGHT, line);
+    }
+}
+
+// Mira en forma de cruz con un hueco en el centro
+pub fn draw_crosshair(framebuffer: &mut Framebuffer, x: isize, y: isize, size: isize) {
+    let gap = 2;
+    framebuffer.set_current_color(0xFFFFFF);
+    framebuffer.set_current_alpha(0.8);
+    framebuffer.line(x - size, y, x - gap, y);
+    framebuffer.line(x + gap, y, x + size, y);
+    framebuffer.line(x, y - size, x, y - gap);
+    framebuffer.line(x, y + gap, x, y + size);
+    framebuffer.set_current_alpha(1.0);
+}
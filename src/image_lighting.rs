@@ -0,0 +1,116 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::environment::Environment;
+use crate::random::Rng;
+
+// Iluminación basada en imagen: el entorno se hornea en una malla
+// equirectangular y se muestrea proporcionalmente a su luminancia.
+pub struct ImageBasedLighting {
+    pub samples: u32,
+    pub intensity: f32,
+    width: usize,
+    height: usize,
+    radiance: Vec<Vec3>,
+    // CDF marginal por filas y CDF condicional dentro de cada fila
+    row_cdf: Vec<f32>,
+    column_cdf: Vec<f32>,
+    weights: Vec<f32>,
+    total_weight: f32,
+}
+
+pub struct EnvironmentSample {
+    pub direction: Vec3,
+    pub radiance: Vec3,
+    pub pdf: f32,
+}
+
+fn luminance(color: &Vec3) -> f32 {
+    0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z
+}
+
+fn uv_to_direction(u: f32, v: f32) -> Vec3 {
+    let theta = v * PI;
+    let phi = (u - 0.5) * 2.0 * PI;
+    Vec3::new(theta.sin() * phi.sin(), theta.cos(), -theta.sin() * phi.cos())
+}
+
+// Primer índice cuyo valor acumulado supera `value`
+fn search(cdf: &[f32], value: f32) -> usize {
+    cdf.partition_point(|&c| c <= value).min(cdf.len() - 1)
+}
+
+impl ImageBasedLighting {
+    pub fn new(environment: &Environment, width: usize, height: usize, samples: u32, intensity: f32) -> Self {
+        let mut radiance = Vec::with_capacity(width * height);
+        let mut weights = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            // Las filas cerca de los polos cubren menos ángulo sólido
+            let sin_theta = (((y as f32 + 0.5) / height as f32) * PI).sin();
+            for x in 0..width {
+                // Supermuestreo 2x2 para no perder detalles pequeños como el sol
+                let mut texel = Vec3::zeros();
+                for (dx, dy) in [(0.25, 0.25), (0.75, 0.25), (0.25, 0.75), (0.75, 0.75)] {
+                    let u = (x as f32 + dx) / width as f32;
+                    let v = (y as f32 + dy) / height as f32;
+                    texel += environment.radiance(&uv_to_direction(u, v)) * 0.25;
+                }
+                radiance.push(texel);
+                weights.push(luminance(&texel).max(1e-6) * sin_theta);
+            }
+        }
+
+        let mut column_cdf = vec![0.0; width * height];
+        let mut row_cdf = vec![0.0; height];
+        let mut total_weight = 0.0;
+
+        for y in 0..height {
+            let mut row_sum = 0.0;
+            for x in 0..width {
+                row_sum += weights[y * width + x];
+                column_cdf[y * width + x] = row_sum;
+            }
+            for x in 0..width {
+                column_cdf[y * width + x] /= row_sum;
+            }
+            total_weight += row_sum;
+            row_cdf[y] = total_weight;
+        }
+        for value in row_cdf.iter_mut() {
+            *value /= total_weight;
+        }
+
+        Self {
+            samples,
+            intensity,
+            width,
+            height,
+            radiance,
+            row_cdf,
+            column_cdf,
+            weights,
+            total_weight,
+        }
+    }
+
+    pub fn sample(&self, rng: &mut Rng) -> EnvironmentSample {
+        let y = search(&self.row_cdf, rng.next_f32());
+        let row = &self.column_cdf[y * self.width..(y + 1) * self.width];
+        let x = search(row, rng.next_f32());
+
+        let u = (x as f32 + rng.next_f32()) / self.width as f32;
+        let v = (y as f32 + rng.next_f32()) / self.height as f32;
+        let direction = uv_to_direction(u, v);
+
+        let index = y * self.width + x;
+        let texel_probability = self.weights[index] / self.total_weight;
+        let pdf_uv = texel_probability * (self.width * self.height) as f32;
+        let sin_theta = (v * PI).sin().max(1e-4);
+
+        EnvironmentSample {
+            direction,
+            radiance: self.radiance[index],
+            pdf: pdf_uv / (2.0 * PI * PI * sin_theta),
+        }
+    }
+}
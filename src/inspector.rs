@@ -0,0 +1,85 @@
+use crate::framebuffer::Framebuffer;
+use crate::ray_intersect::Intersect;
+
+// Objeto bajo el cursor al hacer clic en modo inspector
+pub struct Pick {
+    // Posición del objeto en la lista de la escena
+    pub index: usize,
+    pub name: String,
+    pub intersect: Intersect,
+}
+
+impl Pick {
+    pub fn new(index: usize, name: String, intersect: Intersect) -> Self {
+        Self { index, name, intersect }
+    }
+
+    // Líneas de texto para la consola y el panel de información
+    pub fn describe(&self) -> Vec<String> {
+        let i = &self.intersect;
+        let material = &i.material;
+        let mut lines = vec![
+            format!("OBJETO {}: {}", self.index, self.name),
+            format!("DISTANCIA {:.3}", i.distance),
+            format!("PUNTO {:.2} {:.2} {:.2}", i.point.x, i.point.y, i.point.z),
+            format!("NORMAL {:.2} {:.2} {:.2}", i.normal.x, i.normal.y, i.normal.z),
+            format!(
+                "DIFUSO {} {} {}  ESPECULAR {:.1}",
+                material.diffuse.r, material.diffuse.g, material.diffuse.b, material.specular
+            ),
+            format!(
+                "ALBEDO {:.2} {:.2} {:.2} {:.2}",
+                material.albedo[0], material.albedo[1], material.albedo[2], material.albedo[3]
+            ),
+            format!(
+                "REFRACCION {:.2}  TEXTURA {}  ID {:08X}",
+                material.refractive_index,
+                if material.texture.is_some() { "SI" } else { "NO" },
+                material.id()
+            ),
+        ];
+        if let Some(face) = i.face {
+            lines.push(format!("CARA {:?}", face));
+        }
+        lines
+    }
+}
+
+// Máscara de los píxeles que cubre el objeto seleccionado, para resaltarlo
+pub struct Highlight {
+    pub width: usize,
+    pub height: usize,
+    mask: Vec<bool>,
+}
+
+impl Highlight {
+    pub fn new(width: usize, height: usize, mask: Vec<bool>) -> Self {
+        Self { width, height, mask }
+    }
+
+    fn covers(&self, x: isize, y: isize) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height && self.mask[y as usize * self.width + x as usize]
+    }
+
+    // Tiñe el objeto y dibuja su contorno; la máscara se amplía al tamaño del framebuffer
+    pub fn draw(&self, framebuffer: &mut Framebuffer) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        for y in 0..height {
+            let mask_y = (y * self.height / height) as isize;
+            for x in 0..width {
+                let mask_x = (x * self.width / width) as isize;
+                if !self.covers(mask_x, mask_y) {
+                    continue;
+                }
+                let edge = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .any(|(dx, dy)| !self.covers(mask_x + dx, mask_y + dy));
+                if edge {
+                    framebuffer.set_pixel(x, y, 0xFFD400);
+                } else {
+                    framebuffer.blend_pixel(x, y, 0xFFD400, 0.3);
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use nalgebra_glm::Vec3;
 use crate::color::Color;
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Light {
     pub position: Vec3,
     pub color: Color,
@@ -5,40 +5,69 @@
     mod material;
     mod framebuffer;
     mod block; // Asegúrate de que este módulo esté incluido
-
-
-    use minifb::{Window, WindowOptions, Key};
-    use nalgebra_glm::{Vec3, normalize};
-    use std::time::Duration;
+    mod random;
+    mod motion;
+    mod animation;
+    mod transform;
+    mod csg;
+    mod texture;
+    mod environment;
+    mod scene;
+    mod image_lighting;
+    mod ambient_occlusion;
+    mod volume;
+    mod procedural;
+    mod ray;
+    mod aov;
+    mod denoise;
+    mod progressive;
+    mod resolution;
+    mod hud;
+    mod inspector;
+
+
+    use minifb::{Window, WindowOptions, Key, KeyRepeat, MouseButton, MouseMode, Scale};
+    use nalgebra_glm::{Vec2, Vec3, Vec4, normalize};
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
     use std::f32::consts::PI;
+    use std::rc::Rc;
 
     use crate::color::Color;
-    use crate::ray_intersect::{Intersect, RayIntersect};
+    use crate::ray_intersect::{shadow_transmittance, Intersect, RayIntersect};
     use crate::framebuffer::Framebuffer;
-    use crate::camera::Camera;
+    use crate::camera::{Bokeh, Camera};
     use crate::light::Light;
     use crate::material::Material;
-    use crate::block::Block; // Importa la clase Block
+    use crate::block::{Block, UvRect}; // Importa la clase Block
+    use crate::random::Rng;
+    use crate::motion::{Motion, Moving};
+    use crate::animation::{Interpolation, LightAnimation, ObjectAnimation, Timeline, Track};
+    use crate::transform::{Group, Transform};
+    use crate::csg::Csg;
+    use crate::environment::{color_to_vec3, vec3_to_color, CubeMap, Environment, EquirectangularMap, PreethamSky};
+    use crate::scene::Scene;
+    use crate::image_lighting::ImageBasedLighting;
+    use crate::ambient_occlusion::AmbientOcclusion;
+    use crate::volume::{Density, Fog, Media, Volume};
+    use crate::texture::{Filter, Texture};
+    use crate::ray::{Ray, RayDifferential};
+    use crate::aov::{AovBuffers, AovSample};
+    use crate::denoise::Denoiser;
+    use crate::progressive::Progressive;
+    use crate::resolution::DynamicResolution;
+    use crate::hud::{draw_crosshair, draw_panel, panel_size};
+    use crate::inspector::{Highlight, Pick};
+    use crate::procedural::{Channel, Pattern, ProceduralTexture};
    
 
-    const ORIGIN_BIAS: f32 = 1e-4;
     const SKYBOX_COLOR: Color = Color::new(68, 142, 228);
 
-    fn offset_origin(intersect: &Intersect, direction: &Vec3) -> Vec3 {
-        let offset = intersect.normal * ORIGIN_BIAS;
-        if direction.dot(&intersect.normal) < 0.0 {
-            intersect.point - offset
-        } else {
-            intersect.point + offset
-        }
-    }
-
     fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
         incident - 2.0 * incident.dot(normal) * normal
     }
 
     fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-        let cosi = -incident.dot(normal).max(-1.0).min(1.0);
+        let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
 
         let (n_cosi, eta, n_normal);
 
@@ -61,145 +90,479 @@
         }
     }
 
+    // Fracción de la luz (por canal) que llega al punto. Los objetos
+    // transparentes dejan pasar luz teñida y los efectos se acumulan.
     fn cast_shadow(
         intersect: &Intersect,
         light: &Light,
         objects: &[Box<dyn RayIntersect>], // Use Box<dyn RayIntersect> for polymorphism
-    ) -> f32 {
+        time: f32,
+    ) -> Vec3 {
         let light_dir = (light.position - intersect.point).normalize();
         let light_distance = (light.position - intersect.point).magnitude();
 
-        let shadow_ray_origin = offset_origin(intersect, &light_dir);
-        let mut shadow_intensity = 0.0;
+        let shadow_ray_origin = intersect.offset_origin(&light_dir);
+        shadow_transmittance(&shadow_ray_origin, &light_dir, light_distance, objects, time)
+    }
+
+
 
-        for object in objects.iter() {
-            let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-            if shadow_intersect.is_intersecting && shadow_intersect.distance < light_distance {
-                let distance_ratio = shadow_intersect.distance / light_distance;
-                shadow_intensity = 1.0 - distance_ratio.powf(2.0).min(1.0);
-                break;
+    fn is_occluded(
+        intersect: &Intersect,
+        direction: &Vec3,
+        objects: &[Box<dyn RayIntersect>],
+        time: f32,
+    ) -> bool {
+        let origin = intersect.offset_origin(direction);
+        objects.iter().any(|object| {
+            let hit = object.ray_intersect_at(&origin, direction, time);
+            hit.is_intersecting && hit.distance > 0.0
+        })
+    }
+
+    // Luz difusa del entorno con muestreo por importancia; las direcciones
+    // bloqueadas no aportan, lo que da oclusión ambiental de forma natural
+    fn environment_light(
+        intersect: &Intersect,
+        image_lighting: &ImageBasedLighting,
+        objects: &[Box<dyn RayIntersect>],
+        time: f32,
+        rng: &mut Rng,
+    ) -> Color {
+        let mut irradiance = Vec3::zeros();
+
+        for _ in 0..image_lighting.samples {
+            let sample = image_lighting.sample(rng);
+            let cos_theta = intersect.shading_normal.dot(&sample.direction);
+            if cos_theta <= 0.0 || is_occluded(intersect, &sample.direction, objects, time) {
+                continue;
             }
+            irradiance += sample.radiance * cos_theta / sample.pdf;
         }
 
-        shadow_intensity
+        irradiance /= image_lighting.samples.max(1) as f32 * PI;
+        let diffuse = color_to_vec3(intersect.material.diffuse).component_mul(&irradiance)
+            * intersect.material.albedo[0]
+            * image_lighting.intensity;
+        vec3_to_color(&diffuse)
     }
 
+    // Impacto más cercano junto con el índice del objeto en la escena
+    fn closest_hit(
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        objects: &[Box<dyn RayIntersect>],
+        time: f32,
+    ) -> Option<(usize, Intersect)> {
+        let mut closest = None;
+        let mut zbuffer = f32::INFINITY;
 
+        for (index, object) in objects.iter().enumerate() {
+            let i = object.ray_intersect_at(ray_origin, ray_direction, time);
+            if i.is_intersecting && i.distance < zbuffer {
+                zbuffer = i.distance;
+                closest = Some((index, i));
+            }
+        }
 
-    pub fn cast_ray(
+        closest
+    }
+
+    fn closest_intersect(
         ray_origin: &Vec3,
         ray_direction: &Vec3,
         objects: &[Box<dyn RayIntersect>],
-        lights: &[Light],
-        depth: u32,
+        time: f32,
+    ) -> Intersect {
+        closest_hit(ray_origin, ray_direction, objects, time).map_or_else(Intersect::empty, |(_, intersect)| intersect)
+    }
 
+    pub fn cast_ray(
+        ray: &Ray,
+        scene: &Scene,
+        depth: u32,
+        time: f32,
+        rng: &mut Rng,
+        medium: &Vec3,
+        mut aov: Option<&mut AovSample>,
     ) -> Color {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
         if depth > 3 {
-            return SKYBOX_COLOR;
+            return scene.environment.sample(ray_direction);
         }
 
-        let mut intersect = Intersect::empty();
-        let mut zbuffer = f32::INFINITY;
-
-        for object in objects.iter() {
-            let i = object.ray_intersect(ray_origin, ray_direction);
-            if i.is_intersecting && i.distance < zbuffer {
-                zbuffer = i.distance;
-                intersect = i;
+        let (object_index, mut intersect) = match closest_hit(ray_origin, ray_direction, &scene.objects, time) {
+            Some(hit) => hit,
+            None => {
+                let background = scene.environment.sample(ray_direction);
+                return scene.media.integrate(ray, None, depth, scene, time, rng).apply(background);
             }
+        };
+        let material_id = intersect.material.id();
+
+        // El color de la textura reemplaza al difuso y los mapas de normales
+        // y de relieve dan la normal de sombreado; la geométrica se conserva
+        // para desplazar los rayos secundarios. Las texturas se filtran según
+        // el área que cubre el píxel sobre la superficie.
+        let footprint = ray.footprint(&intersect, scene.texture_filter);
+        intersect.material.diffuse = intersect.material.diffuse_at(&intersect.uv, &footprint);
+        intersect.shading_normal = intersect.material.shading_normal(&intersect.normal, &intersect.tangent, &intersect.bitangent, &intersect.uv, &footprint);
+        for procedural in intersect.material.procedural {
+            procedural.apply(&mut intersect);
         }
 
-        if !intersect.is_intersecting {
-            return SKYBOX_COLOR;
+        if let Some(aov) = aov.as_deref_mut() {
+            aov.depth = intersect.distance;
+            aov.normal = intersect.shading_normal;
+            aov.albedo = color_to_vec3(intersect.material.diffuse);
+            aov.object_id = Some(object_index);
+            aov.material_id = Some(material_id);
         }
 
-        let mut color = Color::black();
+        let mut color = match &scene.image_lighting {
+            Some(image_lighting) => environment_light(&intersect, image_lighting, &scene.objects, time, rng)
+                .scale(1.0 - intersect.material.albedo[2] - intersect.material.albedo[3]),
+            None => Color::black(),
+        };
+
+        if let Some(ambient_occlusion) = &scene.ambient_occlusion {
+            let ambient = ambient_occlusion.shade(&intersect, &scene.objects, time, rng);
+            color = color.add(&ambient.scale(1.0 - intersect.material.albedo[2] - intersect.material.albedo[3]));
+        }
+
+        // Para reflejar y refractar se usa la normal de sombreado salvo que quede
+        // del otro lado de la superficie respecto del rayo; así el lado por el
+        // que entra o sale el rayo lo decide siempre la normal geométrica
+        let bent_normal = if ray_direction.dot(&intersect.shading_normal) * ray_direction.dot(&intersect.normal) > 0.0 {
+            intersect.shading_normal
+        } else {
+            intersect.normal
+        };
 
-        for light in lights.iter() {
+        for light in scene.lights.iter() {
             let light_dir = (light.position - intersect.point).normalize();
             let view_dir = (ray_origin - intersect.point).normalize();
-            let reflect_dir = reflect(&-light_dir, &intersect.normal).normalize();
+            let reflect_dir = reflect(&-light_dir, &intersect.shading_normal).normalize();
 
-            let shadow_intensity = cast_shadow(&intersect, light, objects);
-            let light_intensity = light.intensity * (1.0 - shadow_intensity);
+            let transmittance = cast_shadow(&intersect, light, &scene.objects, time);
+            let light_intensity = light.intensity;
 
-            let diffuse_intensity = intersect.normal.dot(&light_dir).max(0.0).min(1.0);
+            let diffuse_intensity = intersect.shading_normal.dot(&light_dir).clamp(0.0, 1.0);
             let diffuse = intersect.material.diffuse * intersect.material.albedo[0] * diffuse_intensity * light_intensity;
 
             let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.specular);
             let specular = light.color.scale(intersect.material.albedo[1]) * specular_intensity * light_intensity;
 
+            let direct = vec3_to_color(&color_to_vec3(diffuse + specular).component_mul(&transmittance));
+
 
 
             let reflect_color = if intersect.material.albedo[2] > 0.0 {
-                let reflect_dir = reflect(&ray_direction, &intersect.normal).normalize();
-                let reflect_origin = offset_origin(&intersect, &reflect_dir);
-                cast_ray(&reflect_origin, &reflect_dir, objects, lights, depth + 1)
+                let reflect_dir = reflect(ray_direction, &bent_normal).normalize();
+                let reflect_origin = intersect.offset_origin(&reflect_dir);
+                let reflected = ray.bent(&intersect, reflect_origin, reflect_dir, |d| reflect(d, &bent_normal).normalize());
+                // La reflexión se queda en el mismo medio por el que venía el rayo
+                cast_ray(&reflected, scene, depth + 1, time, rng, medium, None)
             } else {
                 Color::black()
             };
 
             let refract_color = if intersect.material.albedo[3] > 0.0 {
-                let refract_dir = refract(&ray_direction, &intersect.normal, intersect.material.refractive_index);
-                let refract_origin = offset_origin(&intersect, &refract_dir);
-                cast_ray(&refract_origin, &refract_dir, objects, lights, depth + 1)
+                let refract_dir = refract(ray_direction, &bent_normal, intersect.material.refractive_index);
+                let refract_origin = intersect.offset_origin(&refract_dir);
+                // Si el rayo entra al objeto, viaja dentro de su material
+                let refract_medium = if refract_dir.dot(&intersect.normal) < 0.0 {
+                    intersect.material.absorption
+                } else {
+                    Vec3::zeros()
+                };
+                let refracted = ray.bent(&intersect, refract_origin, refract_dir, |d| refract(d, &bent_normal, intersect.material.refractive_index));
+                cast_ray(&refracted, scene, depth + 1, time, rng, &refract_medium, None)
             } else {
                 Color::black()
             };
             // Combina los colores con los factores de Fresnel
-            color = color.add(&(diffuse + specular).scale(1.0 - intersect.material.albedo[2] - intersect.material.albedo[3]))
+            let surface = 1.0 - intersect.material.albedo[2] - intersect.material.albedo[3];
+            color = color.add(&direct.scale(surface))
             .add(&reflect_color.scale(intersect.material.albedo[2]))
             .add(&refract_color.scale(intersect.material.albedo[3]));
+
+            if let Some(aov) = aov.as_deref_mut() {
+                aov.diffuse += color_to_vec3(diffuse).component_mul(&transmittance) * surface;
+                aov.specular += color_to_vec3(specular).component_mul(&transmittance) * surface;
+                aov.reflection += color_to_vec3(reflect_color) * intersect.material.albedo[2];
+                aov.refraction += color_to_vec3(refract_color) * intersect.material.albedo[3];
+                aov.shadow += transmittance / scene.lights.len() as f32;
+            }
         }
 
-        color
+        let color = color.add(&intersect.material.emission);
+        let color = absorb(color, medium, intersect.distance);
+        scene.media.integrate(ray, Some(intersect.distance), depth, scene, time, rng).apply(color)
+    }
+
+    // Ley de Beer-Lambert: cada canal se atenúa según la distancia recorrida dentro del medio
+    fn absorb(color: Color, medium: &Vec3, distance: f32) -> Color {
+        if *medium == Vec3::zeros() {
+            return color;
+        }
+        let transmittance = (-medium * distance.max(0.0)).map(f32::exp);
+        vec3_to_color(&color_to_vec3(color).component_mul(&transmittance))
     }
 
     
 
-    pub fn render(framebuffer: &mut Framebuffer, objects: &[Box<dyn RayIntersect>], camera: &Camera, lights: &[Light]) {
-        let width = framebuffer.width as f32;
-        let height = framebuffer.height as f32;
+    // Dirección en espacio de cámara que pasa por el punto (x, y) de la pantalla
+    fn screen_direction(x: f32, y: f32, width: f32, height: f32) -> Vec3 {
         let aspect_ratio = width / height;
         let fov = PI / 3.0;
         let perspective_scale = (fov * 0.5).tan();
 
+        let screen_x = (2.0 * x) / width - 1.0;
+        let screen_y = -(2.0 * y) / height + 1.0;
+
+        let screen_x = screen_x * aspect_ratio * perspective_scale;
+        let screen_y = screen_y * perspective_scale;
+
+        normalize(&Vec3::new(screen_x, screen_y, -1.0))
+    }
+
+    // Diferenciales del rayo primario a partir de las direcciones de los píxeles vecinos
+    fn primary_differential(camera: &Camera, x: f32, y: f32, width: f32, height: f32) -> RayDifferential {
+        let direction = camera.base_change(&screen_direction(x, y, width, height));
+        RayDifferential::primary(
+            camera.base_change(&screen_direction(x + 1.0, y, width, height)) - direction,
+            camera.base_change(&screen_direction(x, y + 1.0, width, height)) - direction,
+        )
+    }
+
+    // Una muestra del rayo de cámara que pasa por el punto `pixel` de la pantalla
+    fn trace_pixel(
+        scene: &Scene,
+        camera: &Camera,
+        pixel: (f32, f32),
+        size: (f32, f32),
+        rng: &mut Rng,
+        aov: Option<&mut AovSample>,
+    ) -> Color {
+        let ((x, y), (width, height)) = (pixel, size);
+        let ray_direction = screen_direction(x, y, width, height);
+        let (origin, direction) = camera.primary_ray(&ray_direction, rng);
+        let differential = primary_differential(camera, x, y, width, height);
+        let time = camera.sample_time(rng);
+
+        cast_ray(&Ray::new(origin, direction, differential), scene, 0, time, rng, &Vec3::zeros(), aov)
+    }
+
+    pub fn render(
+        framebuffer: &mut Framebuffer,
+        scene: &Scene,
+        camera: &Camera,
+        samples_per_pixel: u32,
+        aovs: Option<&mut AovBuffers>,
+    ) {
+        let width = framebuffer.width as f32;
+        let height = framebuffer.height as f32;
+        let samples = samples_per_pixel.max(1);
+
+        // El filtro de ruido necesita las pasadas aunque no se hayan pedido
+        let mut own_aovs = (aovs.is_none() && scene.denoiser.is_some())
+            .then(|| AovBuffers::new(framebuffer.width, framebuffer.height));
+        let mut aovs = aovs.or(own_aovs.as_mut());
+        // La imagen se reemplaza entera: lo acumulado antes ya no le corresponde
+        framebuffer.clear_accumulation();
+
         for y in 0..framebuffer.height {
             for x in 0..framebuffer.width {
-                let screen_x = (2.0 * x as f32) / width - 1.0;
-                let screen_y = -(2.0 * y as f32) / height + 1.0;
+                let mut rng = Rng::for_pixel(x, y, 0);
+                let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+
+                for _ in 0..samples {
+                    // Con una sola muestra se usa la esquina del píxel como antes
+                    let (jitter_x, jitter_y) = if samples > 1 {
+                        (rng.next_f32(), rng.next_f32())
+                    } else {
+                        (0.0, 0.0)
+                    };
+
+                    let mut aov = AovSample::default();
+                    let pixel = (x as f32 + jitter_x, y as f32 + jitter_y);
+                    let sample = trace_pixel(scene, camera, pixel, (width, height), &mut rng, aovs.is_some().then_some(&mut aov));
+                    if let Some(aovs) = aovs.as_deref_mut() {
+                        aovs.accumulate(x, y, &aov, 1.0 / samples as f32);
+                    }
+                    r += sample.r as f32;
+                    g += sample.g as f32;
+                    b += sample.b as f32;
+                }
+
+                let n = samples as f32;
+                let pixel_color = Color::new((r / n) as u8, (g / n) as u8, (b / n) as u8);
 
-                let screen_x = screen_x * aspect_ratio * perspective_scale;
-                let screen_y = screen_y * perspective_scale;
+                framebuffer.set_current_color(pixel_color.to_hex());
+                framebuffer.point(x, y);
+            }
+        }
 
-                let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
-                let rotated_direction = camera.base_change(&ray_direction);
+        if let (Some(denoiser), Some(aovs)) = (&scene.denoiser, aovs) {
+            denoiser.apply(framebuffer, aovs);
+        }
+    }
 
-                let pixel_color = cast_ray(&camera.eye, &rotated_direction, objects, lights, 0);
+    // Vista previa mientras la cámara se mueve: un rayo por bloque de píxeles
+    fn render_preview(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera, block: usize) {
+        let size = (framebuffer.width as f32, framebuffer.height as f32);
 
-                framebuffer.set_current_color(pixel_color.to_hex());
+        for tile in framebuffer.tiles(block) {
+            let mut rng = Rng::for_pixel(tile.x, tile.y, 0);
+            let center = (tile.x as f32 + tile.width as f32 * 0.5, tile.y as f32 + tile.height as f32 * 0.5);
+            let color = trace_pixel(scene, camera, center, size, &mut rng, None).to_hex();
+
+            let mut view = framebuffer.view(tile);
+            for y in 0..tile.height {
+                for x in 0..tile.width {
+                    view.set_pixel(x, y, color);
+                }
+            }
+        }
+    }
+
+    // Agrega una muestra con desplazamiento aleatorio a cada píxel, mosaico por
+    // mosaico, y muestra el promedio acumulado
+    fn render_progressive(framebuffer: &mut Framebuffer, scene: &Scene, camera: &Camera) {
+        let size = (framebuffer.width as f32, framebuffer.height as f32);
+        let pass = framebuffer.accumulated_samples();
+        let mut aovs = scene.denoiser.as_ref().map(|_| AovBuffers::new(framebuffer.width, framebuffer.height));
+
+        for tile in framebuffer.tiles(32) {
+            let mut view = framebuffer.view(tile);
+            for y in 0..tile.height {
+                for x in 0..tile.width {
+                    let (pixel_x, pixel_y) = (tile.x + x, tile.y + y);
+                    let mut rng = Rng::for_pixel(pixel_x, pixel_y, pass);
+                    let pixel = (pixel_x as f32 + rng.next_f32(), pixel_y as f32 + rng.next_f32());
+                    let mut aov = AovSample::default();
+                    let color = trace_pixel(scene, camera, pixel, size, &mut rng, aovs.is_some().then_some(&mut aov));
+                    if let Some(aovs) = aovs.as_mut() {
+                        aovs.accumulate(pixel_x, pixel_y, &aov, 1.0);
+                    }
+                    view.accumulate(x, y, &color_to_vec3(color));
+                }
+            }
+        }
+
+        framebuffer.resolve_accumulation();
+        if let (Some(denoiser), Some(aovs)) = (&scene.denoiser, &aovs) {
+            denoiser.apply(framebuffer, aovs);
+        }
+    }
+
+    // Barra semitransparente en el borde inferior con el avance del refinamiento
+    fn draw_progress(framebuffer: &mut Framebuffer, progress: f32) {
+        let width = framebuffer.width as isize;
+        let top = framebuffer.height as isize - 5;
+
+        framebuffer.set_current_alpha(0.5);
+        framebuffer.set_current_color(0x000000);
+        framebuffer.fill_rect(0, top, width, 5);
+        framebuffer.set_current_color(0xFFFFFF);
+        framebuffer.rect(0, top, width, 5);
+        framebuffer.set_current_alpha(0.9);
+        framebuffer.fill_rect(1, top + 1, ((width - 2) as f32 * progress.clamp(0.0, 1.0)) as isize, 3);
+        framebuffer.set_current_alpha(1.0);
+    }
+
+    // Pasada de depuración: solo la oclusión ambiental en escala de grises
+    pub fn render_ambient_occlusion(
+        framebuffer: &mut Framebuffer,
+        scene: &Scene,
+        camera: &Camera,
+        ambient_occlusion: &AmbientOcclusion,
+    ) {
+        let width = framebuffer.width as f32;
+        let height = framebuffer.height as f32;
+
+        for y in 0..framebuffer.height {
+            for x in 0..framebuffer.width {
+                let mut rng = Rng::for_pixel(x, y, 0);
+                let ray_direction = screen_direction(x as f32, y as f32, width, height);
+                let (origin, direction) = camera.primary_ray(&ray_direction, &mut rng);
+                let time = camera.sample_time(&mut rng);
+
+                let intersect = closest_intersect(&origin, &direction, &scene.objects, time);
+                let visibility = if intersect.is_intersecting {
+                    ambient_occlusion.visibility(&intersect, &scene.objects, time, &mut rng)
+                } else {
+                    1.0
+                };
+
+                let level = (visibility * 255.0) as u8;
+                framebuffer.set_current_color(Color::new(level, level, level).to_hex());
                 framebuffer.point(x, y);
             }
         }
     }
 
+    // Enfoca la cámara en el objeto bajo el cursor
+    fn autofocus(window: &Window, camera: &mut Camera, objects: &[Box<dyn RayIntersect>], width: usize, height: usize) {
+        if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Discard) {
+            let ray_direction = screen_direction(mouse_x, mouse_y, width as f32, height as f32);
+            let world_direction = camera.base_change(&ray_direction);
+            let intersect = closest_intersect(&camera.eye, &world_direction, objects, camera.shutter_open);
 
-    fn main() {
-        let window_width = 400;
-        let window_height = 250;
-        let framebuffer_width = 400;
-        let framebuffer_height =250;
-        let frame_delay = Duration::from_millis(16);
-        let rotation_speed = 0.05; // Ajusta este valor según lo necesario
+            if intersect.is_intersecting && intersect.distance > 0.0 {
+                camera.focus_on(&ray_direction, intersect.distance);
+            }
+        }
+    }
 
-        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    // Objeto que ve el rayo que pasa por el cursor
+    fn pick_object(window: &Window, camera: &Camera, objects: &[Box<dyn RayIntersect>], width: usize, height: usize) -> Option<Pick> {
+        let (mouse_x, mouse_y) = window.get_mouse_pos(MouseMode::Discard)?;
+        let ray_direction = camera.base_change(&screen_direction(mouse_x, mouse_y, width as f32, height as f32));
+        closest_hit(&camera.eye, &ray_direction, objects, camera.shutter_open)
+            .map(|(index, intersect)| Pick::new(index, objects[index].name(), intersect))
+    }
+
+    // Píxeles en que el objeto `index` es el primero que ve la cámara
+    fn object_mask(objects: &[Box<dyn RayIntersect>], camera: &Camera, index: usize, width: usize, height: usize) -> Highlight {
+        let mut mask = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let direction = screen_direction(x as f32 + 0.5, y as f32 + 0.5, width as f32, height as f32);
+                let hit = closest_hit(&camera.eye, &camera.base_change(&direction), objects, camera.shutter_open);
+                mask[y * width + x] = matches!(hit, Some((hit_index, _)) if hit_index == index);
+            }
+        }
+        Highlight::new(width, height, mask)
+    }
 
-        let mut window = Window::new(
-            "Refractor",
-            window_width,
-            window_height,
-            WindowOptions::default(),
-        ).unwrap();
+
+    fn build_objects() -> Vec<Box<dyn RayIntersect>> {
+        // Texturas procedurales de la escena
+        static WOOD: [ProceduralTexture; 2] = [
+            ProceduralTexture::new(Pattern::Wood { scale: 1.0, rings: 4.0 }, Channel::Diffuse(Color::new(150, 75, 0), Color::new(95, 45, 5))),
+            ProceduralTexture::new(Pattern::Fbm { scale: 6.0, octaves: 3 }, Channel::Bump(0.01)),
+        ];
+        static CHECKER: [ProceduralTexture; 1] = [
+            ProceduralTexture::new(Pattern::Checker { scale: 5.0 }, Channel::Diffuse(Color::new(150, 75, 0), Color::new(230, 210, 170))),
+        ];
+        static LAVA_GLOW: [ProceduralTexture; 1] = [
+            ProceduralTexture::new(Pattern::Turbulence { scale: 12.0, octaves: 4 }, Channel::Emission(Color::new(255, 200, 60))),
+        ];
+        static RUBBER_WEAR: [ProceduralTexture; 1] = [
+            ProceduralTexture::new(Pattern::Voronoi { scale: 25.0 }, Channel::Roughness(0.2, 0.9)),
+        ];
+        static MARBLE: [ProceduralTexture; 1] = [
+            ProceduralTexture::new(Pattern::Marble { scale: 4.0, veins: 3.0 }, Channel::Diffuse(Color::new(235, 235, 225), Color::new(70, 80, 90))),
+        ];
+        static RUST: [ProceduralTexture; 1] = [
+            ProceduralTexture::new(Pattern::Noise { scale: 40.0 }, Channel::Roughness(0.3, 1.0)),
+        ];
+        static BARK: [ProceduralTexture; 2] = [
+            ProceduralTexture::new(Pattern::Turbulence { scale: 30.0, octaves: 3 }, Channel::Diffuse(Color::new(85, 55, 30), Color::new(40, 25, 15))),
+            ProceduralTexture::new(Pattern::Turbulence { scale: 30.0, octaves: 3 }, Channel::Bump(0.004)),
+        ];
 
         let rubber = Material::new(
             Color::new(80, 0, 0),
@@ -207,15 +570,17 @@
             [0.9, 0.1, 0.0, 0.0],
             0.0,
 
-        );
+        ).with_procedural(&RUBBER_WEAR);
 
+        // Piedra con ladrillos en relieve a partir de un mapa de normales
+        let stone_normals: &'static Texture = Box::leak(Box::new(stone_height_texture().to_normal_map(2.0)));
         let ivory = Material::new(
             Color::new(100, 100, 80),
             0.0,
             [0.6, 0.3, 0.6, 0.0],
             0.0,
 
-        );
+        ).with_normal_map(stone_normals);
 
 
         let block_material = Material::new(
@@ -224,7 +589,8 @@
             [0.8, 0.2, 0.0, 0.0],
             0.0,
 
-        );
+        ).with_procedural(&WOOD);
+        let ceiling_material = block_material.with_procedural(&CHECKER);
 
         let water_material = Material::new(
             Color::new(0, 0, 255),
@@ -233,6 +599,10 @@
             0.0,
 
         );
+        // El agua del estanque de la planta baja no absorbe por sí misma: con
+        // --fog la tiñe el volumen de agua de `build_media`
+        let pool_water_material = water_material;
+        let water_material = water_material.with_tint(2.0); // El agua se vuelve más azul cuanto más profunda
         
             let mirror = Material::new(
             Color::new(255, 255, 255), // El color no importa mucho aquí
@@ -249,6 +619,17 @@
             2.0, // Alta reflectividad para simular lava brillante
 
         );
+        // Relieve de la lava a partir del primer cuadro de su textura animada
+        let lava_material = match Texture::load("src/imagenes/lava_flow.png") {
+            Ok(strip) => {
+                let frame = Texture::from_fn(32, 32, |x, y| strip.texel(x as i64, y as i64));
+                lava_material.with_bump_map(Box::leak(Box::new(frame)), 0.01).with_procedural(&LAVA_GLOW)
+            }
+            Err(error) => {
+                println!("No se pudo cargar la textura de lava: {}", error);
+                lava_material.with_procedural(&LAVA_GLOW)
+            }
+        };
 
 
         let mut objects: Vec<Box<dyn RayIntersect>> = vec![];
@@ -258,184 +639,576 @@
         let room_width = 1.5; // Aumentar el ancho de la habitación
         let room_depth = 1.5; // Aumentar la profundidad de la habitación
         
-        // Pared trasera
-        objects.push(Box::new(Block { min: Vec3::new(-room_width, -1.0, -room_depth - wall_thickness), max: Vec3::new(room_width, wall_height - 1.0, -room_depth), material: block_material }));
+        // Pared trasera con dos ventanas recortadas
+        let back_wall = Block::new(Vec3::new(-room_width, -1.0, -room_depth - wall_thickness), Vec3::new(room_width, wall_height - 1.0, -room_depth), block_material);
+        let windows = Csg::union(
+            Box::new(Block::new(Vec3::new(0.6, 0.1, -room_depth - 0.2), Vec3::new(0.9, 0.6, -room_depth + 0.1), block_material)),
+            Box::new(Block::new(Vec3::new(1.0, 0.1, -room_depth - 0.2), Vec3::new(1.3, 0.6, -room_depth + 0.1), block_material)),
+        );
+        objects.push(Box::new(Csg::difference(Box::new(back_wall), Box::new(windows))));
         
         // Pared izquierda
-        objects.push(Box::new(Block { min: Vec3::new(-room_width - wall_thickness, -1.0, -room_depth), max: Vec3::new(-room_width, wall_height - 1.0, room_depth), material: block_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-room_width - wall_thickness, -1.0, -room_depth), Vec3::new(-room_width, wall_height - 1.0, room_depth), block_material)));
         
         
         // Suelo
-        objects.push(Box::new(Block { min: Vec3::new(-room_width, -1.0, -room_depth), max: Vec3::new(room_width, -1.0 + wall_thickness, room_depth), material: block_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-room_width, -1.0, -room_depth), Vec3::new(room_width, -1.0 + wall_thickness, room_depth), block_material)));
         
         // Techo a la mitad
-        objects.push(Box::new(Block { min: Vec3::new(-room_width, wall_height / 2.0, -room_depth), max: Vec3::new(room_width/2.0, (wall_height / 2.0) + wall_thickness, room_depth), material: block_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-room_width, wall_height / 2.0, -room_depth), Vec3::new(room_width/2.0, (wall_height / 2.0) + wall_thickness, room_depth), ceiling_material)));
         
         //Bloque de lava
-        objects.push(Box::new(Block { min: Vec3::new(-room_width - wall_thickness, -0.9, -room_depth-wall_thickness), max: Vec3::new(-1.0, -0.5, -1.0), material: lava_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-room_width - wall_thickness, -0.9, -room_depth-wall_thickness), Vec3::new(-1.0, -0.5, -1.0), lava_material)));
 
         //-1.6 -0.9 -1.6       -1.0 -0.5  -1.0 
          
         //Empezare agregando bloques raros en medio esperando que pueda agregarles texturas.
         //Bloque de el medio medio
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.9, -0.3), max: Vec3::new(0.1, -0.7, -0.1), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.9, -0.3), Vec3::new(0.1, -0.7, -0.1), ivory)));
         
         //Bloque a la izquierda del de enmedio
         //Otro bloque a la par: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.9, -0.3), max: Vec3::new(-0.1, -0.7, -0.1), material: lava_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.9, -0.3), Vec3::new(-0.1, -0.7, -0.1), lava_material)));
 
         //Otro bloque a la par: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.9, -0.3), max: Vec3::new(-0.3, -0.7, -0.1), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.9, -0.3), Vec3::new(-0.3, -0.7, -0.1), ivory)));
 
         //Empezar con los bloques a la derecha del medio.
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.9, -0.3), max: Vec3::new(0.3, -0.7, -0.1), material: lava_material }));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.9, -0.3), Vec3::new(0.3, -0.7, -0.1), lava_material)));
 
         //Empezar con los bloques a la derecha del medio.
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.9, -0.3), max: Vec3::new(0.5, -0.7, -0.1), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.9, -0.3), Vec3::new(0.5, -0.7, -0.1), ivory)));
 
 
         //Aqui ira el suelo de arriba: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.1, -0.3), max: Vec3::new(0.1, 0.1, -0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.1, -0.3), max: Vec3::new(-0.1, 0.1, -0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, -0.3), max: Vec3::new(-0.3, 0.1, -0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.1, -0.3), max: Vec3::new(0.3, 0.1, -0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, -0.3), max: Vec3::new(0.5, 0.1, -0.5), material: ivory }));  
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.1, -0.3), Vec3::new(0.1, 0.1, -0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.1, -0.3), Vec3::new(-0.1, 0.1, -0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.1, -0.3), Vec3::new(-0.3, 0.1, -0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.1, -0.3), Vec3::new(0.3, 0.1, -0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.1, -0.3), Vec3::new(0.5, 0.1, -0.5), ivory)));  
 
         //Aqui ira el suelo de arriba: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.1, -0.3), max: Vec3::new(0.1, 0.1, -0.1), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.1, -0.3), max: Vec3::new(-0.1, 0.1, -0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, -0.3), max: Vec3::new(-0.3, 0.1, -0.1), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.1, -0.3), max: Vec3::new(0.3, 0.1, -0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, -0.3), max: Vec3::new(0.5, 0.1, -0.1), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.1, -0.3), Vec3::new(0.1, 0.1, -0.1), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.1, -0.3), Vec3::new(-0.1, 0.1, -0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.1, -0.3), Vec3::new(-0.3, 0.1, -0.1), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.1, -0.3), Vec3::new(0.3, 0.1, -0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.1, -0.3), Vec3::new(0.5, 0.1, -0.1), ivory)));
 
          //Aqui ira el suelo de arriba: 
-         objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.1, -0.1), max: Vec3::new(0.1, 0.1, 0.1), material: ivory }));
-         objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.1, -0.1), max: Vec3::new(-0.1, 0.1, 0.1), material: lava_material }));
-         objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, -0.1), max: Vec3::new(-0.3, 0.1, 0.1), material: mirror }));
-         objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.1, -0.1), max: Vec3::new(0.3, 0.1, 0.1), material: lava_material }));
-         objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, -0.1), max: Vec3::new(0.5, 0.1, 0.1), material: rubber }));    
+         objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.1, -0.1), Vec3::new(0.1, 0.1, 0.1), ivory)));
+         objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.1, -0.1), Vec3::new(-0.1, 0.1, 0.1), lava_material)));
+         objects.push(Box::new(Moving::new(
+             Block::new(Vec3::new(-0.5 , -0.1, -0.1), Vec3::new(-0.3, 0.1, 0.1), mirror),
+             Motion::Linear { velocity: Vec3::new(0.0, 0.0, 0.1) },
+         )));
+         objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.1, -0.1), Vec3::new(0.3, 0.1, 0.1), lava_material)));
+         objects.push(Box::new(Moving::new(
+             Block::new(Vec3::new(0.3 , -0.1, -0.1), Vec3::new(0.5, 0.1, 0.1), rubber),
+             // Cae y rebota mientras el obturador está abierto
+             Motion::Keyframes(Track::new()
+                 .key(0.0, Vec3::new(0.0, 0.0, 0.0), Interpolation::Linear)
+                 .key(0.7, Vec3::new(0.0, -0.2, 0.0), Interpolation::Bezier)
+                 .key(1.0, Vec3::new(0.0, -0.15, 0.0), Interpolation::Linear)),
+         )));    
 
         //Aqui ira el suelo de arriba: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.1, 0.1), max: Vec3::new(0.1, 0.1, 0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.1, 0.1), max: Vec3::new(-0.1, 0.1, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, 0.1), max: Vec3::new(-0.3, 0.1, 0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.1, 0.1), max: Vec3::new(0.3, 0.1, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, 0.1), max: Vec3::new(0.5, 0.1, 0.3), material: ivory }));   
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.1, 0.1), Vec3::new(0.1, 0.1, 0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.1, 0.1), Vec3::new(-0.1, 0.1, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.1, 0.1), Vec3::new(-0.3, 0.1, 0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.1, 0.1), Vec3::new(0.3, 0.1, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.1, 0.1), Vec3::new(0.5, 0.1, 0.3), ivory)));   
 
         //Aqui ira el suelo de arriba: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.1, 0.3), max: Vec3::new(0.1, 0.1, 0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.1, 0.3), max: Vec3::new(-0.1, 0.1, 0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, 0.3), max: Vec3::new(-0.3, 0.1, 0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.1, 0.3), max: Vec3::new(0.3, 0.1, 0.5), material: water_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, 0.3), max: Vec3::new(0.5, 0.1, 0.5), material: ivory }));   
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.1, 0.3), Vec3::new(0.1, 0.1, 0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.1, 0.3), Vec3::new(-0.1, 0.1, 0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.1, 0.3), Vec3::new(-0.3, 0.1, 0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.1, 0.3), Vec3::new(0.3, 0.1, 0.5), pool_water_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.1, 0.3), Vec3::new(0.5, 0.1, 0.5), ivory)));   
 
          
 //---------------------------------------------------------------------//
         //Aqui ira el suelo de arriba segunda capa: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.1, -0.3), max: Vec3::new(0.1, 0.3, -0.5), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.1, -0.3), max: Vec3::new(-0.1, 0.3, -0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, -0.3), max: Vec3::new(-0.3, 0.3, -0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.1, -0.3), max: Vec3::new(0.3, 0.3, -0.5), material: rubber }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, -0.3), max: Vec3::new(0.5, 0.3, -0.5), material: ivory }));  
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.1, -0.3), Vec3::new(0.1, 0.3, -0.5), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.1, -0.3), Vec3::new(-0.1, 0.3, -0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, -0.3), Vec3::new(-0.3, 0.3, -0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.1, -0.3), Vec3::new(0.3, 0.3, -0.5), rubber)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, -0.3), Vec3::new(0.5, 0.3, -0.5), ivory)));  
 
         //Aqui ira el suelo de arriba segunda capa: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.1, -0.3), max: Vec3::new(0.1, 0.3, -0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.1, -0.3), max: Vec3::new(-0.1, 0.3, -0.1), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, -0.3), max: Vec3::new(-0.3, 0.3, -0.1), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.1, -0.3), max: Vec3::new(0.3, 0.3, -0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, -0.3), max: Vec3::new(0.5, 0.3, -0.1), material: block_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.1, -0.3), Vec3::new(0.1, 0.3, -0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.1, -0.3), Vec3::new(-0.1, 0.3, -0.1), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, -0.3), Vec3::new(-0.3, 0.3, -0.1), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.1, -0.3), Vec3::new(0.3, 0.3, -0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, -0.3), Vec3::new(0.5, 0.3, -0.1), block_material)));
 
          //Aqui ira el suelo de arriba segunda capa: 
-         objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.1, -0.1), max: Vec3::new(0.1, 0.3, 0.1), material: ivory }));
-         objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.1, -0.1), max: Vec3::new(-0.1, 0.3, 0.1), material: lava_material }));
-         objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, -0.1), max: Vec3::new(-0.3, 0.3, 0.1), material: block_material }));
-         objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.1, -0.1), max: Vec3::new(0.3, 0.3, 0.1), material: ivory }));
-         objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, -0.1), max: Vec3::new(0.5, 0.3, 0.1), material: water_material }));    
+         objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.1, -0.1), Vec3::new(0.1, 0.3, 0.1), ivory)));
+         objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.1, -0.1), Vec3::new(-0.1, 0.3, 0.1), lava_material)));
+         objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, -0.1), Vec3::new(-0.3, 0.3, 0.1), block_material)));
+         objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.1, -0.1), Vec3::new(0.3, 0.3, 0.1), ivory)));
+         objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, -0.1), Vec3::new(0.5, 0.3, 0.1), water_material)));    
 
         //Aqui ira el suelo de arriba segunda capa: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.1, 0.1), max: Vec3::new(0.1, 0.3, 0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.1, 0.1), max: Vec3::new(-0.1, 0.3, 0.3), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, 0.1), max: Vec3::new(-0.3, 0.3, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.1, 0.1), max: Vec3::new(0.3, 0.3, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, 0.1), max: Vec3::new(0.5, 0.3, 0.3), material: water_material }));   
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.1, 0.1), Vec3::new(0.1, 0.3, 0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.1, 0.1), Vec3::new(-0.1, 0.3, 0.3), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, 0.1), Vec3::new(-0.3, 0.3, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.1, 0.1), Vec3::new(0.3, 0.3, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, 0.1), Vec3::new(0.5, 0.3, 0.3), water_material)));   
 
         //Aqui ira el suelo de arriba segunda capa: 
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.1, 0.3), max: Vec3::new(0.1, 0.3, 0.5), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.1, 0.3), max: Vec3::new(-0.1, 0.3, 0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, 0.3), max: Vec3::new(-0.3, 0.3, 0.5), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.1, 0.3), max: Vec3::new(0.3, 0.3, 0.5), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, 0.3), max: Vec3::new(0.5, 0.3, 0.5), material: block_material }));  
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.1, 0.3), Vec3::new(0.1, 0.3, 0.5), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.1, 0.3), Vec3::new(-0.1, 0.3, 0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, 0.3), Vec3::new(-0.3, 0.3, 0.5), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.1, 0.3), Vec3::new(0.3, 0.3, 0.5), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, 0.3), Vec3::new(0.5, 0.3, 0.5), block_material)));  
 
 
         // Bloques randoms hasta arriba
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.3, -0.5), max: Vec3::new(0.1, 0.5, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.3, -0.5), max: Vec3::new(-0.1, 0.5, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.3, -0.3), max: Vec3::new(-0.3, 0.5, -0.1), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.3, -0.5), max: Vec3::new(0.3, 0.5, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.3, -0.3), max: Vec3::new(0.5, 0.5, -0.1), material: block_material }));  
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.3, -0.1), max: Vec3::new(0.5, 0.5, 0.1), material: block_material }));  
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.3, 0.3), max: Vec3::new(0.5, 0.5, 0.5), material: block_material }));  
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.3, 0.3), max: Vec3::new(-0.3, 0.5, 0.5), material: block_material }));  
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.3, -0.5), Vec3::new(0.1, 0.5, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.3, -0.5), Vec3::new(-0.1, 0.5, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.3, -0.3), Vec3::new(-0.3, 0.5, -0.1), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.3, -0.5), Vec3::new(0.3, 0.5, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.3, -0.3), Vec3::new(0.5, 0.5, -0.1), block_material)));  
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.3, -0.1), Vec3::new(0.5, 0.5, 0.1), block_material)));  
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.3, 0.3), Vec3::new(0.5, 0.5, 0.5), block_material)));  
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.3, 0.3), Vec3::new(-0.3, 0.5, 0.5), block_material)));  
 
         
         // Bloques randoms hasta arriba mas arriba
-        //objects.push(Box::new(Block { min: Vec3::new(-0.1 , 0.5, -0.5), max: Vec3::new(0.1, 0.7, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , 0.5, -0.5), max: Vec3::new(-0.1, 0.7, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.5, -0.5), max: Vec3::new(-0.3, 0.7, -0.3), material: block_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , 0.5, -0.5), max: Vec3::new(0.3, 0.7, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.5, -0.5), max: Vec3::new(0.5, 0.7, -0.3), material: block_material }));  
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.5, -0.3), max: Vec3::new(0.5, 0.7, -0.1), material: block_material }));  
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.7, -0.5), max: Vec3::new(0.5, 0.9, -0.3), material: block_material }));  
+        //objects.push(Box::new(Block::new(Vec3::new(-0.1 , 0.5, -0.5), Vec3::new(0.1, 0.7, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , 0.5, -0.5), Vec3::new(-0.1, 0.7, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.5, -0.5), Vec3::new(-0.3, 0.7, -0.3), block_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , 0.5, -0.5), Vec3::new(0.3, 0.7, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.5, -0.5), Vec3::new(0.5, 0.7, -0.3), block_material)));  
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.5, -0.3), Vec3::new(0.5, 0.7, -0.1), block_material)));  
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.7, -0.5), Vec3::new(0.5, 0.9, -0.3), block_material)));  
 
 
         //Otro bloque a la par, aqui iran el flujo de los bloques de hasta el fondo
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.9, -0.5), max: Vec3::new(-0.1, -0.7, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.9, -0.5), max: Vec3::new(-0.3, -0.7, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.9, -0.5), max: Vec3::new(0.1, -0.7, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.9, -0.5), max: Vec3::new(0.3, -0.7, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.9, -0.5), max: Vec3::new(0.5, -0.7, -0.3), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.9, -0.5), Vec3::new(-0.1, -0.7, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.9, -0.5), Vec3::new(-0.3, -0.7, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.9, -0.5), Vec3::new(0.1, -0.7, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.9, -0.5), Vec3::new(0.3, -0.7, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.9, -0.5), Vec3::new(0.5, -0.7, -0.3), ivory)));
 
         //Otro bloque a la par, aqui iran el flujo de los bloques de hasta el fondo pero arriba
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.7, -0.5), max: Vec3::new(-0.1, -0.5, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.7, -0.5), max: Vec3::new(-0.3, -0.5, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.7, -0.5), max: Vec3::new(0.1, -0.5, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.7, -0.5), max: Vec3::new(0.3, -0.5, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.7, -0.5), max: Vec3::new(0.5, -0.5, -0.3), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.7, -0.5), Vec3::new(-0.1, -0.5, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.7, -0.5), Vec3::new(-0.3, -0.5, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.7, -0.5), Vec3::new(0.1, -0.5, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.7, -0.5), Vec3::new(0.3, -0.5, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.7, -0.5), Vec3::new(0.5, -0.5, -0.3), ivory)));
 
         //Otro bloque a la par, aqui iran el flujo de los bloques de hasta el fondo pero arriba dos capas
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.5, -0.5), max: Vec3::new(-0.1, -0.3, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.5, -0.5), max: Vec3::new(-0.3, -0.3, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.5, -0.5), max: Vec3::new(0.1, -0.3, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.5, -0.5), max: Vec3::new(0.3, -0.3, -0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.5, -0.5), max: Vec3::new(0.5, -0.3, -0.3), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.5, -0.5), Vec3::new(-0.1, -0.3, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.5, -0.5), Vec3::new(-0.3, -0.3, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.5, -0.5), Vec3::new(0.1, -0.3, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.5, -0.5), Vec3::new(0.3, -0.3, -0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.5, -0.5), Vec3::new(0.5, -0.3, -0.3), ivory)));
 
         //Otro bloque aqui iran hacia arriba los de hasta el fondo izquierda
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.3, -0.5), max: Vec3::new(-0.3, -0.1, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.1, -0.5), max: Vec3::new(-0.3, 0.1, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.1, -0.5), max: Vec3::new(-0.3, 0.3, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , 0.3, -0.5), max: Vec3::new(-0.3, 0.5, -0.3), material: lava_material }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.3, -0.5), Vec3::new(-0.3, -0.1, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.1, -0.5), Vec3::new(-0.3, 0.1, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.1, -0.5), Vec3::new(-0.3, 0.3, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , 0.3, -0.5), Vec3::new(-0.3, 0.5, -0.3), lava_material)));
 
         //Otro bloque aqui iran hacia arriba los de hasta el fondo derecha
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.3, -0.5), max: Vec3::new(0.5, -0.1, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.1, -0.5), max: Vec3::new(0.5, 0.1, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.1, -0.5), max: Vec3::new(0.5, 0.3, -0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , 0.3, -0.5), max: Vec3::new(0.5, 0.5, -0.3), material: lava_material }));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.3, -0.5), Vec3::new(0.5, -0.1, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.1, -0.5), Vec3::new(0.5, 0.1, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.1, -0.5), Vec3::new(0.5, 0.3, -0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , 0.3, -0.5), Vec3::new(0.5, 0.5, -0.3), lava_material)));
 
         //iran el flujo de los bloques una capa enfrente de los de enmedio
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.9, -0.1), max: Vec3::new(-0.1, -0.7, 0.1), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.9, -0.1), max: Vec3::new(-0.3, -0.7, 0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.9, -0.1), max: Vec3::new(0.1, -0.7, 0.1), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.9, -0.1), max: Vec3::new(0.3, -0.7, 0.1), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.9, -0.1), max: Vec3::new(0.5, -0.7, 0.1), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.9, -0.1), Vec3::new(-0.1, -0.7, 0.1), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.9, -0.1), Vec3::new(-0.3, -0.7, 0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.9, -0.1), Vec3::new(0.1, -0.7, 0.1), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.9, -0.1), Vec3::new(0.3, -0.7, 0.1), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.9, -0.1), Vec3::new(0.5, -0.7, 0.1), ivory)));
 
         
         //iran el flujo de los bloques dos capas enfrente de los de enmedio
-        objects.push(Box::new(Block { min: Vec3::new(-0.3 , -0.9, 0.1), max: Vec3::new(-0.1, -0.7, 0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.5 , -0.9, 0.1), max: Vec3::new(-0.3, -0.7, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(-0.1 , -0.9, 0.1), max: Vec3::new(0.1, -0.7, 0.3), material: lava_material }));
-        objects.push(Box::new(Block { min: Vec3::new(0.1 , -0.9, 0.1), max: Vec3::new(0.3, -0.7, 0.3), material: ivory }));
-        objects.push(Box::new(Block { min: Vec3::new(0.3 , -0.9, 0.1), max: Vec3::new(0.5, -0.7, 0.3), material: ivory }));
+        objects.push(Box::new(Block::new(Vec3::new(-0.3 , -0.9, 0.1), Vec3::new(-0.1, -0.7, 0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.5 , -0.9, 0.1), Vec3::new(-0.3, -0.7, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(-0.1 , -0.9, 0.1), Vec3::new(0.1, -0.7, 0.3), lava_material)));
+        objects.push(Box::new(Block::new(Vec3::new(0.1 , -0.9, 0.1), Vec3::new(0.3, -0.7, 0.3), ivory)));
+        objects.push(Box::new(Block::new(Vec3::new(0.3 , -0.9, 0.1), Vec3::new(0.5, -0.7, 0.3), ivory)));
+
+        // Reja con huecos transparentes: los rayos de cámara y de sombra pasan entre los barrotes
+        let grate: &'static Texture = Box::leak(Box::new(grate_texture()));
+        let fence_material = Material::new(Color::new(90, 90, 100), 10.0, [0.8, 0.2, 0.0, 0.0], 0.0)
+            .with_texture(grate)
+            .with_alpha_cutoff(0.5)
+            .with_procedural(&RUST);
+        objects.push(Box::new(Block::new(Vec3::new(-1.2, -0.9, 0.5), Vec3::new(-0.5, -0.4, 0.55), fence_material)));
+
+        // Geometría compartida: una pequeña torre que se instancia rotada y escalada
+        let tower: Rc<dyn RayIntersect> = Rc::new(Group::new(vec![
+            Box::new(Block::new(Vec3::new(-0.1, 0.0, -0.1), Vec3::new(0.1, 0.2, 0.1), ivory)),
+            Box::new(Block::new(Vec3::new(-0.06, 0.2, -0.06), Vec3::new(0.06, 0.3, 0.06), lava_material)),
+        ]));
+        let up = Vec3::new(0.0, 1.0, 0.0);
+        objects.push(Box::new(Transform::identity(tower.clone()).rotate(PI / 4.0, up).translate(Vec3::new(1.0, -0.9, 1.0))));
+        objects.push(Box::new(Transform::identity(tower.clone()).rotate(PI / 9.0, up).translate(Vec3::new(1.0, -0.9, 0.4))));
+        objects.push(Box::new(Transform::identity(tower).scale(Vec3::new(1.0, 1.5, 1.0)).translate(Vec3::new(1.0, -0.9, -0.2))));
+
+        // Columna octogonal: intersección de un prisma cuadrado con su copia girada 45 grados
+        let marble = Material::new(Color::new(235, 235, 225), 50.0, [0.7, 0.3, 0.1, 0.0], 0.0).with_procedural(&MARBLE);
+        let column_section = || Block::new(Vec3::new(-0.15, 0.0, -0.15), Vec3::new(0.15, 1.2, 0.15), marble);
+        let column: Rc<dyn RayIntersect> = Rc::new(Csg::intersection(
+            Box::new(column_section()),
+            Box::new(Transform::identity(Rc::new(column_section())).rotate(PI / 4.0, up)),
+        ));
+        objects.push(Box::new(Transform::identity(column).translate(Vec3::new(-1.1, -0.9, 1.0))));
+
+        // Bloque de césped: cada cara toma su casilla del atlas (arriba, lado, abajo)
+        let terrain: &'static Texture = Box::leak(Box::new(terrain_atlas()));
+        let grass = Material::new(Color::new(90, 160, 60), 0.0, [0.9, 0.05, 0.0, 0.0], 0.0).with_texture(terrain);
+        let tile = |column: f32| UvRect::new(Vec2::new(column / 3.0, 0.0), Vec2::new((column + 1.0) / 3.0, 1.0));
+        objects.push(Box::new(
+            Block::new(Vec3::new(0.65, -0.9, -1.35), Vec3::new(0.9, -0.65, -1.1), grass).with_atlas(tile(0.0), tile(1.0), tile(2.0)),
+        ));
+
+        // Tronco: anillos de madera en las tapas y corteza en los lados
+        let bark = Material::new(Color::new(70, 45, 25), 0.0, [0.9, 0.05, 0.0, 0.0], 0.0).with_procedural(&BARK);
+        objects.push(Box::new(
+            Block::new(Vec3::new(1.0, -0.9, -1.35), Vec3::new(1.2, -0.5, -1.15), block_material).with_faces(block_material, bark, block_material),
+        ));
+
+        objects
+    }
+
+    // Atlas de 3 casillas de 16x16: césped, lado de césped con tierra y tierra
+    fn terrain_atlas() -> Texture {
+        Texture::from_fn(48, 16, |x, y| {
+            let grain = 0.85 + 0.15 * ((x * 7 + y * 13) % 5) as f32 / 4.0;
+            let grass = Vec4::new(0.35 * grain, 0.65 * grain, 0.2 * grain, 1.0);
+            let dirt = Vec4::new(0.5 * grain, 0.35 * grain, 0.2 * grain, 1.0);
+            match x / 16 {
+                0 => grass,
+                // El césped cuelga unos píxeles irregulares sobre el lado
+                1 if y < 3 + (x * 5) % 3 => grass,
+                _ => dirt,
+            }
+        })
+    }
+
+    // Alturas de un muro de ladrillos: juntas hundidas y ladrillos desfasados por fila
+    fn stone_height_texture() -> Texture {
+        Texture::from_fn(32, 32, |x, y| {
+            let row = y / 8;
+            let offset = if row % 2 == 0 { 0 } else { 8 };
+            let joint = y % 8 == 0 || (x + offset) % 16 == 0;
+            let height = if joint { 0.0 } else { 0.8 + 0.05 * ((x * 7 + y * 13) % 5) as f32 };
+            Vec4::new(height, height, height, 1.0)
+        })
+    }
 
+    // Textura RGBA de barrotes de hierro; lo demás queda con alfa cero
+    fn grate_texture() -> Texture {
+        Texture::from_fn(32, 32, |x, y| {
+            let bar = x % 8 < 2 || y % 16 < 2;
+            if bar {
+                let shade = 0.35 + 0.1 * ((x + y) % 3) as f32;
+                Vec4::new(shade, shade, shade + 0.05, 1.0)
+            } else {
+                Vec4::new(0.0, 0.0, 0.0, 0.0)
+            }
+        })
+    }
+
+    fn build_lights(lava_light_active: bool) -> Vec<Light> {
+        let lava_light = Light::new(
+            Vec3::new(-1.3, -0.7, -1.3), // Alinea la luz con el centro del bloque de lava // -1.3 -0.7 -1.3
+            Color::new(255, 100, 0),    // Color brillante para la lava
+            if !lava_light_active { 5.0 } else { 0.0 }, // Cambia la intensidad según el estado 
+            true,              
+        );
+        
+        
+        let sunlight = Light::new(
+            Vec3::new(5.0, 5.0, 5.0),
+            Color::new(255, 100, 0),
+            0.5,
+            true,
+        );
+
+        vec![
+            lava_light,
+            sunlight,
+        ]
+    }
+
+    fn procedural_environment(index: usize) -> Environment {
+        match index {
+            0 => Environment::Sky(PreethamSky::new(Vec3::new(5.0, 5.0, 5.0), 3.0, 0.6)), // Sol alineado con la luz solar
+            1 => Environment::Gradient {
+                zenith: Color::new(20, 70, 170),
+                horizon: SKYBOX_COLOR,
+                ground: Color::new(60, 50, 40),
+            },
+            _ => Environment::Solid(SKYBOX_COLOR),
+        }
+    }
+
+    // --envmap <archivo.hdr> carga un mapa equirectangular y --cubemap <carpeta>
+    // las seis caras px, nx, py, ny, pz, nz en PNG
+    fn load_environment(args: &[String]) -> Environment {
+        let value_of = |flag: &str| args.iter().position(|arg| arg == flag).and_then(|i| args.get(i + 1));
+
+        if let Some(path) = value_of("--envmap") {
+            match EquirectangularMap::load(path, 1.0) {
+                Ok(map) => return Environment::Equirectangular(map),
+                Err(error) => println!("No se pudo cargar {}: {}", path, error),
+            }
+        }
+        if let Some(directory) = value_of("--cubemap") {
+            match CubeMap::load(directory) {
+                Ok(cube) => return Environment::CubeMap(cube),
+                Err(error) => println!("No se pudo cargar {}: {}", directory, error),
+            }
+        }
+
+        procedural_environment(0)
+    }
+
+    // Humo que sube del bloque de lava y se disipa con la altura
+    fn smoke_density(point: &Vec3) -> f32 {
+        let swirl = (point.x * 14.0 + point.y * 6.0).sin() * (point.z * 14.0 - point.y * 4.0).cos() * 0.5 + 0.5;
+        let fade = 1.0 - ((point.y + 0.5) / 1.5).clamp(0.0, 1.0);
+        swirl * fade * 4.0
+    }
+
+    // Niebla ligera, agua que absorbe el rojo y humo sobre la lava
+    fn build_media() -> Media {
+        let mut media = Media::empty();
+        media.fog = Some(Fog { color: Color::new(200, 200, 210), density: 0.04 });
+
+        // Agua del estanque de la planta baja: absorbe el rojo y dispersa el azul.
+        // Su bloque refractivo no tiene absorción propia, así que no se atenúa dos veces
+        media.volumes.push(Volume {
+            min: Vec3::new(0.1, -0.1, 0.3),
+            max: Vec3::new(0.3, 0.1, 0.5),
+            density: Density::Constant(1.0),
+            absorption: Vec3::new(4.0, 1.2, 0.3),
+            scattering: Vec3::new(0.2, 0.4, 0.6),
+        });
+        media.volumes.push(Volume {
+            min: Vec3::new(-1.6, -0.5, -1.6),
+            max: Vec3::new(-1.0, 0.8, -1.0),
+            density: Density::Field(smoke_density),
+            absorption: Vec3::new(0.5, 0.5, 0.5),
+            scattering: Vec3::new(1.0, 1.0, 1.0),
+        });
+
+        media
+    }
+
+    fn default_ambient_occlusion() -> AmbientOcclusion {
+        AmbientOcclusion::new(8, 0.5, Color::new(255, 255, 255), 0.3)
+    }
+
+    fn default_denoiser() -> Denoiser {
+        Denoiser::new(4, 0.5, 64.0, 0.05)
+    }
+
+    // Vuelta de cámara de 4 segundos con la luz de lava parpadeando
+    // Vuelta de presentación; agrega a la escena un bloque que gira y flota
+    // sobre la casa durante la animación
+    fn turntable(scene: &mut Scene) -> Timeline {
+        let mut timeline = Timeline::turntable(4.0, 24.0, Vec3::new(0.0, 0.0, 0.0), 5.0, 0.5);
+
+        let gold = Material::new(Color::new(220, 180, 60), 50.0, [0.7, 0.3, 0.0, 0.0], 0.0);
+        let cube: Rc<dyn RayIntersect> = Rc::new(Block::new(Vec3::new(-0.1, -0.1, -0.1), Vec3::new(0.1, 0.1, 0.1), gold));
+        let spinning_cube = ObjectAnimation {
+            position: Track::new()
+                .key(0.0, Vec3::new(0.0, 2.1, 0.0), Interpolation::Bezier)
+                .key(2.0, Vec3::new(0.0, 2.4, 0.0), Interpolation::Bezier)
+                .key(4.0, Vec3::new(0.0, 2.1, 0.0), Interpolation::Bezier),
+            rotation: Track::new()
+                .key(0.0, Vec3::new(0.0, 0.0, 0.0), Interpolation::Linear)
+                .key(4.0, Vec3::new(PI / 4.0, 2.0 * PI, 0.0), Interpolation::Linear),
+            scale: Track::new()
+                .key(0.0, Vec3::repeat(1.0), Interpolation::Bezier)
+                .key(2.0, Vec3::repeat(1.4), Interpolation::Bezier)
+                .key(4.0, Vec3::repeat(1.0), Interpolation::Bezier),
+            ..ObjectAnimation::new(cube)
+        };
+        timeline.objects.push((scene.objects.len(), spinning_cube.clone()));
+        scene.objects.push(Box::new(spinning_cube.transform_at(0.0)));
+
+        let flicker = Track::new()
+            .key(0.0, 5.0, Interpolation::Bezier)
+            .key(1.0, 3.0, Interpolation::Bezier)
+            .key(2.0, 5.5, Interpolation::Bezier)
+            .key(3.0, 2.5, Interpolation::Bezier)
+            .key(4.0, 5.0, Interpolation::Bezier);
+        timeline.lights.push((0, LightAnimation { intensity: flicker, ..Default::default() }));
+
+        timeline
+    }
+
+    // Renderiza cada fotograma de la línea de tiempo como frame_0000.png, frame_0001.png, ...
+    fn render_sequence(
+        framebuffer: &mut Framebuffer,
+        scene: &mut Scene,
+        timeline: &Timeline,
+        output_dir: &str,
+        samples_per_pixel: u32,
+        ambient_occlusion_only: bool,
+        write_aovs: bool,
+    ) {
+        if let Err(error) = std::fs::create_dir_all(output_dir) {
+            println!("No se pudo crear la carpeta {}: {}", output_dir, error);
+            return;
+        }
+
+        let mut camera = Camera::new(
+            Vec3::new(0.0, 0.0, 5.0),
+            Vec3::new(0.0, 0.0, 0.0),
+            Vec3::new(0.0, 1.0, 0.0)
+        );
+        scene.lights = build_lights(false);
+
+        for frame in 0..timeline.frame_count() {
+            let time = timeline.frame_time(frame);
+            timeline.apply(time, &mut camera, &mut scene.lights, &mut scene.objects);
+
+            // Obturador de 180 grados: medio fotograma de exposición
+            camera.shutter_open = time;
+            camera.shutter_close = time + 0.5 / timeline.fps;
+
+            let mut aovs = write_aovs.then(|| AovBuffers::new(framebuffer.width, framebuffer.height));
+            match (&scene.ambient_occlusion, ambient_occlusion_only) {
+                (Some(ambient_occlusion), true) => render_ambient_occlusion(framebuffer, scene, &camera, ambient_occlusion),
+                _ => render(framebuffer, scene, &camera, samples_per_pixel, aovs.as_mut()),
+            }
+
+            let path = format!("{}/frame_{:04}.png", output_dir, frame);
+            if let Err(error) = framebuffer.save(&path) {
+                println!("No se pudo guardar el fotograma {}: {}", path, error);
+                return;
+            }
+            if let Some(aovs) = &aovs {
+                if let Err(error) = aovs.save_exr(&format!("{}/frame_{:04}", output_dir, frame)) {
+                    println!("No se pudieron guardar las pasadas del fotograma {}: {}", frame, error);
+                    return;
+                }
+            }
+            println!("Fotograma {} / {}: {}", frame + 1, timeline.frame_count(), path);
+        }
+    }
+
+
+    // Fecha y hora UTC actual como "AAAA-MM-DD_HH-MM-SS" para nombrar capturas
+    fn timestamp() -> String {
+        let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs()) as i64;
+        let (days, time) = (seconds.div_euclid(86_400), seconds.rem_euclid(86_400));
+
+        // Conversión de días desde 1970 a fecha civil (calendario gregoriano)
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z - era * 146_097;
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+        format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, time / 3600, time % 3600 / 60, time % 60)
+    }
+
+    // Guarda lo que muestra la ventana; en alta calidad vuelve a renderizar la
+    // misma vista al doble del tamaño de la ventana (no del render, que depende
+    // de la escala dinámica) y con más muestras
+    fn capture(framebuffer: &Framebuffer, scene: &Scene, camera: &Camera, high_quality: bool, window_size: (usize, usize)) {
+        let output_dir = "capturas";
+        if let Err(error) = std::fs::create_dir_all(output_dir) {
+            println!("No se pudo crear la carpeta de capturas: {}", error);
+            return;
+        }
+
+        let path = format!("{}/captura_{}.png", output_dir, timestamp());
+        let result = if high_quality {
+            println!("Renderizando captura en alta calidad...");
+            let mut high_quality = Framebuffer::new(window_size.0 * 2, window_size.1 * 2);
+            render(&mut high_quality, scene, camera, 16, None);
+            high_quality.save(&path)
+        } else {
+            framebuffer.save(&path)
+        };
+
+        match result {
+            Ok(()) => println!("Captura guardada: {}", path),
+            Err(error) => println!("No se pudo guardar la captura: {}", error),
+        }
+    }
+
+    // Ventana redimensionable, o sin bordes y ampliada hasta llenar la pantalla
+    fn open_window(width: usize, height: usize, fullscreen: bool) -> Window {
+        let options = if fullscreen {
+            WindowOptions {
+                borderless: true,
+                title: false,
+                topmost: true,
+                scale: Scale::FitScreen,
+                ..WindowOptions::default()
+            }
+        } else {
+            WindowOptions {
+                resize: true,
+                ..WindowOptions::default()
+            }
+        };
+
+        let mut window = Window::new("Refractor", width, height, options).unwrap();
+        if fullscreen {
+            window.set_position(0, 0);
+        }
+        window
+    }
+
+    fn main() {
+        let mut window_width = 400;
+        let mut window_height = 250;
+        let framebuffer_width = 400;
+        let framebuffer_height =250;
+        let frame_delay = Duration::from_millis(16);
+        let rotation_speed = 0.05; // Ajusta este valor según lo necesario
+
+        let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+
+        let args: Vec<String> = std::env::args().collect();
+        let mut scene = Scene::new(build_objects(), build_lights(false), load_environment(&args));
+        // --ibl ilumina la escena con el entorno desde el inicio
+        if args.iter().any(|arg| arg == "--ibl") {
+            scene.enable_image_lighting(8, 1.0);
+        }
+        // --ao agrega luz ambiental con oclusión; --ao-only guarda solo esa pasada
+        if args.iter().any(|arg| arg == "--ao" || arg == "--ao-only") {
+            scene.ambient_occlusion = Some(default_ambient_occlusion());
+        }
+        // --denoise filtra el ruido de cada imagen con las pasadas auxiliares
+        if args.iter().any(|arg| arg == "--denoise") {
+            scene.denoiser = Some(default_denoiser());
+        }
+        // --fog agrega niebla, agua y humo con rayos de luz volumétricos
+        if args.iter().any(|arg| arg == "--fog") {
+            scene.media = build_media();
+        }
+
+        // Modo por lotes: cargo run -- --sequence <carpeta> renderiza una vuelta completa a PNG;
+        // con --aov también guarda las pasadas de cada fotograma en EXR
+        if let Some(index) = args.iter().position(|arg| arg == "--sequence") {
+            let output_dir = args.get(index + 1).map(String::as_str).unwrap_or("frames");
+            let ambient_occlusion_only = args.iter().any(|arg| arg == "--ao-only");
+            let write_aovs = args.iter().any(|arg| arg == "--aov");
+            let timeline = turntable(&mut scene);
+            render_sequence(&mut framebuffer, &mut scene, &timeline, output_dir, 4, ambient_occlusion_only, write_aovs);
+            return;
+        }
+
+        let mut window = open_window(window_width, window_height, false);
+        let mut fullscreen = false;
 
         let mut lava_light_active = true; // Variable para controlar el estado de la luz
 
@@ -445,7 +1218,45 @@
             Vec3::new(0.0, 1.0, 0.0) // Vector 'up'
         );
 
+        let mut samples_per_pixel = 1;
+        let mut environment_index = 0;
+        let mut show_ambient_occlusion = false;
+        // Con la cámara quieta la imagen se refina acumulando hasta 64 muestras
+        let mut progressive = Progressive::new(4, 64);
+        let mut progressive_mode = true;
+        // La resolución interna se ajusta para mantener ~30 cuadros por segundo
+        // y se amplía al tamaño de la ventana
+        let mut resolution = DynamicResolution::new(30.0, 0.25, 1.0);
+        // Imagen del tamaño de la ventana: el render ampliado más el panel de información
+        let mut display = Framebuffer::new(window_width, window_height);
+        let mut show_hud = true;
+        // K activa el inspector: el clic selecciona un objeto en vez de enfocar
+        let mut inspector_mode = false;
+        let mut show_highlight = true;
+        let mut pick: Option<Pick> = None;
+        let mut highlight: Option<Highlight> = None;
+        let mut mouse_was_down = false;
+
         while window.is_open() {
+            let frame_start = Instant::now();
+
+            // F11 alterna la pantalla completa; minifb no puede cambiar una ventana
+            // abierta, así que se vuelve a crear
+            if window.is_key_pressed(Key::F11, KeyRepeat::No) {
+                fullscreen = !fullscreen;
+                window = open_window(window_width, window_height, fullscreen);
+            }
+            // En modo ventana la imagen sigue el tamaño de la ventana; en pantalla
+            // completa minifb amplía el búfer y el tamaño lógico no cambia
+            if !fullscreen {
+                let (width, height) = window.get_size();
+                if width > 0 && height > 0 && (width, height) != (window_width, window_height) {
+                    window_width = width;
+                    window_height = height;
+                    display.resize(window_width, window_height);
+                }
+            }
+
             if window.is_key_down(Key::Left) {
                 camera.orbit(rotation_speed, 0.0); 
             }
@@ -469,37 +1280,220 @@
             if window.is_key_down(Key::W) {
                 camera.zoom(0.1); // Alejar
             }
+            // Profundidad de campo: clic para enfocar, +/- apertura, B forma del bokeh, M muestras
+            // El clic actúa solo al presionar el botón, no en cada cuadro mientras sigue abajo
+            let mouse_down = window.get_mouse_down(MouseButton::Left);
+            let clicked = mouse_down && !mouse_was_down;
+            mouse_was_down = mouse_down;
+            if clicked && inspector_mode {
+                pick = pick_object(&window, &camera, &scene.objects, window_width, window_height);
+                highlight = None;
+                match &pick {
+                    Some(pick) => pick.describe().iter().for_each(|line| println!("{}", line)),
+                    None => println!("Ningún objeto bajo el cursor"),
+                }
+            } else if clicked {
+                autofocus(&window, &mut camera, &scene.objects, window_width, window_height);
+            }
+            if window.is_key_pressed(Key::Equal, KeyRepeat::Yes) {
+                camera.aperture = (camera.aperture + 0.01).min(0.5);
+            }
+            if window.is_key_pressed(Key::Minus, KeyRepeat::Yes) {
+                camera.aperture = (camera.aperture - 0.01).max(0.0);
+            }
+            if window.is_key_pressed(Key::B, KeyRepeat::No) {
+                camera.bokeh = match camera.bokeh {
+                    Bokeh::Disk => Bokeh::Polygon { blades: 6, rotation: 0.0 },
+                    Bokeh::Polygon { .. } => Bokeh::Disk,
+                };
+            }
+            if window.is_key_pressed(Key::M, KeyRepeat::No) {
+                samples_per_pixel = if samples_per_pixel == 1 { 8 } else { 1 };
+            }
+            // T abre o cierra el obturador para ver el desenfoque de movimiento
+            if window.is_key_pressed(Key::T, KeyRepeat::No) {
+                camera.shutter_close = if camera.shutter_close > camera.shutter_open { camera.shutter_open } else { camera.shutter_open + 1.0 };
+            }
+
+            // E cambia entre los entornos procedurales
+            if window.is_key_pressed(Key::E, KeyRepeat::No) {
+                environment_index = (environment_index + 1) % 3;
+                scene.environment = procedural_environment(environment_index);
+                if scene.image_lighting.is_some() {
+                    scene.enable_image_lighting(8, 1.0);
+                }
+            }
+            // I activa la iluminación basada en el entorno
+            if window.is_key_pressed(Key::I, KeyRepeat::No) {
+                if scene.image_lighting.is_some() {
+                    scene.image_lighting = None;
+                } else {
+                    scene.enable_image_lighting(8, 1.0);
+                }
+            }
+
+            // O activa la oclusión ambiental y P muestra solo esa pasada
+            if window.is_key_pressed(Key::O, KeyRepeat::No) {
+                scene.ambient_occlusion = match scene.ambient_occlusion {
+                    Some(_) => None,
+                    None => Some(default_ambient_occlusion()),
+                };
+            }
+            if window.is_key_pressed(Key::P, KeyRepeat::No) {
+                show_ambient_occlusion = !show_ambient_occlusion;
+            }
+
+            // X cambia el filtrado de texturas: bilineal, trilineal o anisotrópico
+            if window.is_key_pressed(Key::X, KeyRepeat::No) {
+                scene.texture_filter = match scene.texture_filter {
+                    Filter::Bilinear => Filter::Trilinear,
+                    Filter::Trilinear => Filter::Anisotropic(8),
+                    Filter::Anisotropic(_) => Filter::Bilinear,
+                };
+            }
+
+            // N activa el filtro de ruido guiado por normales, albedo y profundidad
+            if window.is_key_pressed(Key::N, KeyRepeat::No) {
+                scene.denoiser = match scene.denoiser {
+                    Some(_) => None,
+                    None => Some(default_denoiser()),
+                };
+            }
+
+            // F activa los medios participativos (niebla, agua y humo)
+            if window.is_key_pressed(Key::F, KeyRepeat::No) {
+                scene.media = if scene.media.is_empty() { build_media() } else { Media::empty() };
+            }
+
             if window.is_key_down(Key::L) { //L para la luz de la lava 
             lava_light_active = !lava_light_active; // Alterna el estado
-            println!("Luz de lava activa: {}", lava_light_active); // Debug
         }
 
-        let lava_light = Light::new(
-            Vec3::new(-1.3, -0.7, -1.3), // Alinea la luz con el centro del bloque de lava // -1.3 -0.7 -1.3
-            Color::new(255, 100, 0),    // Color brillante para la lava
-            if lava_light_active ==false { 5.0 } else { 0.0 }, // Cambia la intensidad según el estado 
-            true,              
-        );
-        
-        
-        let sunlight = Light::new(
-            Vec3::new(5.0, 5.0, 5.0),
-            Color::new(255, 100, 0),
-            0.5,
-            true,
-        );
+        scene.lights = build_lights(lava_light_active);
 
-        let lights = vec![
-            lava_light,
-            sunlight,
-        ];
+            // F12 guarda una captura; con Shift la vuelve a renderizar en alta calidad
+            if window.is_key_pressed(Key::F12, KeyRepeat::No) {
+                let high_quality = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+                capture(&framebuffer, &scene, &camera, high_quality, (window_width, window_height));
+            }
+
+            // H muestra u oculta el panel de información y la mira
+            if window.is_key_pressed(Key::H, KeyRepeat::No) {
+                show_hud = !show_hud;
+            }
+
+            // K activa el modo inspector y J el resaltado del objeto seleccionado
+            if window.is_key_pressed(Key::K, KeyRepeat::No) {
+                inspector_mode = !inspector_mode;
+                if !inspector_mode {
+                    pick = None;
+                    highlight = None;
+                }
+            }
+            if window.is_key_pressed(Key::J, KeyRepeat::No) {
+                show_highlight = !show_highlight;
+            }
 
-            render(&mut framebuffer, &objects, &camera, &lights);
+            // R alterna entre el refinamiento progresivo y renderizar todo en cada cuadro
+            if window.is_key_pressed(Key::R, KeyRepeat::No) {
+                progressive_mode = !progressive_mode;
+            }
+            // Cualquier otro cambio de configuración también invalida lo acumulado;
+            // las capturas (con o sin Shift), el panel y el inspector no cambian la escena
+            let moved = progressive.track(&camera, &scene.lights);
+            let reconfigured = window
+                .get_keys_pressed(KeyRepeat::No)
+                .iter()
+                .any(|key| !matches!(key, Key::F12 | Key::LeftShift | Key::RightShift | Key::H | Key::K | Key::J));
+            if moved || reconfigured {
+                framebuffer.clear_accumulation();
+            }
 
+            // Con la cámara quieta se refina a resolución completa; en movimiento
+            // la escala la decide el control de resolución dinámica
+            let (render_width, render_height) = if progressive_mode && !moved {
+                (window_width, window_height)
+            } else {
+                resolution.render_size(window_width, window_height)
+            };
+            if (render_width, render_height) != (framebuffer.width, framebuffer.height) {
+                framebuffer.resize(render_width, render_height);
+            }
+
+            let refining = progressive_mode && !moved && !(show_ambient_occlusion && scene.ambient_occlusion.is_some());
+            match (&scene.ambient_occlusion, show_ambient_occlusion) {
+                (Some(ambient_occlusion), true) => render_ambient_occlusion(&mut framebuffer, &scene, &camera, ambient_occlusion),
+                _ if progressive_mode && moved => render_preview(&mut framebuffer, &scene, &camera, progressive.preview_block),
+                _ if progressive_mode => {
+                    if !progressive.is_converged(&framebuffer) {
+                        render_progressive(&mut framebuffer, &scene, &camera);
+                    }
+                }
+                _ => render(&mut framebuffer, &scene, &camera, samples_per_pixel, None),
+            }
+
+            framebuffer.upscale_into(&mut display.buffer, window_width, window_height);
+            // La máscara se calcula a la resolución del render y solo cuando cambia la vista
+            if let (Some(selected), true) = (&pick, show_highlight) {
+                let stale = highlight.as_ref().is_none_or(|mask| {
+                    moved || (mask.width, mask.height) != (framebuffer.width, framebuffer.height)
+                });
+                if stale {
+                    highlight = Some(object_mask(&scene.objects, &camera, selected.index, framebuffer.width, framebuffer.height));
+                }
+                if let Some(mask) = &highlight {
+                    mask.draw(&mut display);
+                }
+            }
+            if show_hud {
+                let mut lines = vec![
+                    format!("FPS {:.0}  {:.1} MS  {}X{}", resolution.fps(), resolution.frame_time() * 1000.0, framebuffer.width, framebuffer.height),
+                    format!("CAMARA {:.2} {:.2} {:.2}", camera.eye.x, camera.eye.y, camera.eye.z),
+                    format!("ENFOQUE {:.2}  APERTURA {:.2}", camera.focus_distance, camera.aperture),
+                    if progressive_mode {
+                        format!("MUESTRAS {}/{}", framebuffer.accumulated_samples(), progressive.max_samples)
+                    } else {
+                        format!("MUESTRAS {} POR PIXEL", samples_per_pixel)
+                    },
+                    format!("FILTRO {:?}  RUIDO {}", scene.texture_filter, if scene.denoiser.is_some() { "SI" } else { "NO" }),
+                ];
+                for (index, light) in scene.lights.iter().enumerate() {
+                    let on = light.active && light.intensity > 0.0;
+                    lines.push(format!("LUZ {}: {} {:.1}", index, if on { "ON" } else { "OFF" }, light.intensity));
+                }
+                draw_panel(&mut display, 4, 4, &lines);
+                draw_crosshair(&mut display, window_width as isize / 2, window_height as isize / 2, 6);
+            }
+            // La barra va sobre la imagen ampliada para no quedar en el render ni en las capturas
+            if refining && !progressive.is_converged(&framebuffer) {
+                let progress = framebuffer.accumulated_samples() as f32 / progressive.max_samples as f32;
+                draw_progress(&mut display, progress);
+            }
+            if inspector_mode {
+                let lines = pick.as_ref().map_or_else(|| vec![String::from("INSPECTOR: CLIC EN UN OBJETO")], Pick::describe);
+                let (_, panel_height) = panel_size(&lines);
+                draw_panel(&mut display, 4, window_height as isize - panel_height - 4, &lines);
+            }
             window
-                .update_with_buffer(&framebuffer.buffer, framebuffer_width, framebuffer_height)
+                .update_with_buffer(&display.buffer, window_width, window_height)
                 .unwrap();
 
+            // Se mide antes de la pausa fija, que no es tiempo de render
+            let frame_time = frame_start.elapsed();
             std::thread::sleep(frame_delay);
+
+            // Mientras se refina no se ajusta la escala: esos cuadros no son interactivos
+            if progressive_mode && !moved {
+                resolution.record(frame_time);
+            } else {
+                resolution.update(frame_time);
+            }
+            window.set_title(&format!(
+                "Refractor | {:.0} FPS | {:.1} ms | {}x{}",
+                resolution.fps(),
+                resolution.frame_time() * 1000.0,
+                framebuffer.width,
+                framebuffer.height,
+            ));
         }
     }
\ No newline at end of file
@@ -1,4 +1,7 @@
+use nalgebra_glm::{Vec2, Vec3};
 use crate::color::Color;
+use crate::procedural::ProceduralTexture;
+use crate::texture::{Footprint, Texture};
 
 #[derive(Debug, Clone, Copy)]
 pub struct Material {
@@ -6,6 +9,21 @@ pub struct Material {
     pub specular: f32,
     pub albedo: [f32; 4],
     pub refractive_index: f32,
+    // Coeficientes de absorción (r, g, b) por unidad de distancia dentro del material
+    pub absorption: Vec3,
+    // Textura de color; las texturas se cargan una vez y viven todo el programa
+    pub texture: Option<&'static Texture>,
+    // Los texels con alfa menor a este umbral se descartan (0.0 lo desactiva)
+    pub alpha_cutoff: f32,
+    // Mapa de normales en espacio tangente (RGB -> XYZ)
+    pub normal_map: Option<&'static Texture>,
+    // Mapa de alturas en escala de grises y qué tanto inclina la normal
+    pub bump_map: Option<&'static Texture>,
+    pub bump_strength: f32,
+    // Luz propia que se suma al sombreado
+    pub emission: Color,
+    // Texturas procedurales evaluadas en el punto de impacto, en orden
+    pub procedural: &'static [ProceduralTexture],
 }
 
 impl Material {
@@ -20,6 +38,14 @@ impl Material {
             specular,
             albedo,
             refractive_index,
+            absorption: Vec3::zeros(),
+            texture: None,
+            alpha_cutoff: 0.0,
+            normal_map: None,
+            bump_map: None,
+            bump_strength: 0.0,
+            emission: Color::black(),
+            procedural: &[],
         }
     }
 
@@ -29,6 +55,110 @@ impl Material {
             specular: 0.0,
             albedo: [0.0; 4],
             refractive_index: 0.0,
+            absorption: Vec3::zeros(),
+            texture: None,
+            alpha_cutoff: 0.0,
+            normal_map: None,
+            bump_map: None,
+            bump_strength: 0.0,
+            emission: Color::black(),
+            procedural: &[],
         }
     }
+
+    // Absorción derivada del color difuso: el medio deja pasar su propio color
+    // y se tiñe más cuanto más grueso es
+    pub fn with_tint(mut self, strength: f32) -> Self {
+        let channel = |c: u8| -((c as f32 / 255.0).max(0.01)).ln() * strength;
+        self.absorption = Vec3::new(channel(self.diffuse.r), channel(self.diffuse.g), channel(self.diffuse.b));
+        self
+    }
+
+    pub fn with_texture(mut self, texture: &'static Texture) -> Self {
+        self.texture = Some(texture);
+        self
+    }
+
+    pub fn with_alpha_cutoff(mut self, threshold: f32) -> Self {
+        self.alpha_cutoff = threshold;
+        self
+    }
+
+    pub fn with_normal_map(mut self, normal_map: &'static Texture) -> Self {
+        self.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn with_bump_map(mut self, bump_map: &'static Texture, strength: f32) -> Self {
+        self.bump_map = Some(bump_map);
+        self.bump_strength = strength;
+        self
+    }
+
+    pub fn with_procedural(mut self, procedural: &'static [ProceduralTexture]) -> Self {
+        self.procedural = procedural;
+        self
+    }
+
+    // Normal de sombreado con el detalle de los mapas de normales y de relieve
+    pub fn shading_normal(&self, normal: &Vec3, tangent: &Vec3, bitangent: &Vec3, uv: &Vec2, footprint: &Footprint) -> Vec3 {
+        let mut shading = *normal;
+
+        if let Some(normal_map) = self.normal_map {
+            let texel = normal_map.sample_footprint(uv, footprint);
+            let local = texel.xyz() * 2.0 - Vec3::new(1.0, 1.0, 1.0);
+            shading = (tangent * local.x + bitangent * local.y + shading * local.z).normalize();
+        }
+
+        if let Some(bump_map) = self.bump_map {
+            let height = |u: f32, v: f32| {
+                let texel = bump_map.sample_footprint(&Vec2::new(u, v), footprint);
+                (texel.x + texel.y + texel.z) / 3.0
+            };
+            let du = 1.0 / bump_map.width as f32;
+            let dv = 1.0 / bump_map.height as f32;
+            let center = height(uv.x, uv.y);
+            let slope_u = (height(uv.x + du, uv.y) - center) / du;
+            let slope_v = (height(uv.x, uv.y + dv) - center) / dv;
+
+            shading = (shading - (tangent * slope_u + bitangent * slope_v) * self.bump_strength).normalize();
+        }
+
+        shading
+    }
+
+    pub fn diffuse_at(&self, uv: &Vec2, footprint: &Footprint) -> Color {
+        match self.texture {
+            Some(texture) => {
+                let texel = texture.sample_footprint(uv, footprint);
+                let channel = |c: f32| (c * 255.0).clamp(0.0, 255.0) as u8;
+                Color::new(channel(texel.x), channel(texel.y), channel(texel.z))
+            }
+            None => self.diffuse,
+        }
+    }
+
+    // Verdadero si el punto cae en una zona transparente de la textura
+    pub fn is_cutout(&self, uv: &Vec2) -> bool {
+        match self.texture {
+            Some(texture) if self.alpha_cutoff > 0.0 => texture.sample(uv.x, uv.y).w < self.alpha_cutoff,
+            _ => false,
+        }
+    }
+
+    // Identificador estable derivado de las propiedades del material, para la pasada de ID
+    pub fn id(&self) -> u32 {
+        let mut hash: u32 = 0x811C_9DC5;
+        let mut mix = |value: u32| hash = (hash ^ value).wrapping_mul(0x0100_0193);
+        mix(self.diffuse.to_hex());
+        mix(self.specular.to_bits());
+        for albedo in self.albedo {
+            mix(albedo.to_bits());
+        }
+        mix(self.refractive_index.to_bits());
+        mix(self.texture.map_or(0, |texture| texture as *const Texture as usize as u32));
+        mix(self.procedural.as_ptr() as usize as u32);
+        mix(self.emission.to_hex());
+        hash
+    }
 }
@@ -0,0 +1,66 @@
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::{RayIntersect, Intersect, Interval};
+use crate::animation::Track;
+
+#[derive(Debug, Clone)]
+pub enum Motion {
+    // Desplazamiento constante por unidad de tiempo
+    Linear { velocity: Vec3 },
+    // Desplazamiento animado con fotogramas clave
+    Keyframes(Track<Vec3>),
+}
+
+impl Motion {
+    pub fn offset(&self, time: f32) -> Vec3 {
+        match self {
+            Motion::Linear { velocity } => velocity * time,
+            Motion::Keyframes(track) => track.sample(time).unwrap_or_else(Vec3::zeros),
+        }
+    }
+}
+
+// Envuelve cualquier primitiva y la desplaza según el tiempo del rayo
+pub struct Moving<T: RayIntersect> {
+    pub object: T,
+    pub motion: Motion,
+}
+
+impl<T: RayIntersect> Moving<T> {
+    pub fn new(object: T, motion: Motion) -> Self {
+        Self { object, motion }
+    }
+}
+
+impl<T: RayIntersect> RayIntersect for Moving<T> {
+    fn name(&self) -> String {
+        format!("{} en movimiento", self.object.name())
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect {
+        // Mover el rayo en sentido contrario equivale a mover el objeto
+        let offset = self.motion.offset(time);
+        let local_origin = ray_origin - offset;
+
+        let mut intersect = self.object.ray_intersect_at(&local_origin, ray_direction, time);
+        if intersect.is_intersecting {
+            intersect.point += offset;
+        }
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Interval> {
+        let offset = self.motion.offset(time);
+        let local_origin = ray_origin - offset;
+
+        let mut intervals = self.object.ray_intervals(&local_origin, ray_direction, time);
+        for interval in intervals.iter_mut() {
+            interval.enter.point += offset;
+            interval.exit.point += offset;
+        }
+        intervals
+    }
+}
@@ -0,0 +1,201 @@
+use nalgebra_glm::Vec3;
+use crate::color::Color;
+use crate::ray_intersect::Intersect;
+
+// Patrones 3D evaluados en el punto de impacto; todos devuelven valores en [0, 1]
+#[derive(Debug, Clone, Copy)]
+pub enum Pattern {
+    Noise { scale: f32 },
+    Fbm { scale: f32, octaves: u32 },
+    Turbulence { scale: f32, octaves: u32 },
+    Checker { scale: f32 },
+    // Anillos concéntricos alrededor del eje Y, deformados con ruido
+    Wood { scale: f32, rings: f32 },
+    // Vetas sinusoidales perturbadas por turbulencia
+    Marble { scale: f32, veins: f32 },
+    // Distancia al punto característico más cercano (celdas de Voronoi)
+    Voronoi { scale: f32 },
+}
+
+// Qué propiedad del material controla el patrón
+#[derive(Debug, Clone, Copy)]
+pub enum Channel {
+    // Mezcla entre dos colores
+    Diffuse(Color, Color),
+    // Rugosidad entre dos valores; se traduce al exponente especular de Phong
+    Roughness(f32, f32),
+    // Color emitido escalado por el patrón
+    Emission(Color),
+    // Relieve: inclina la normal según el gradiente del patrón
+    Bump(f32),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ProceduralTexture {
+    pub pattern: Pattern,
+    pub channel: Channel,
+}
+
+impl ProceduralTexture {
+    pub const fn new(pattern: Pattern, channel: Channel) -> Self {
+        Self { pattern, channel }
+    }
+
+    pub fn apply(&self, intersect: &mut Intersect) {
+        let point = intersect.point;
+        match self.channel {
+            Channel::Diffuse(a, b) => {
+                let t = self.pattern.value(&point);
+                intersect.material.diffuse = a * (1.0 - t) + b * t;
+            }
+            Channel::Roughness(min, max) => {
+                let roughness = (min + (max - min) * self.pattern.value(&point)).clamp(0.01, 1.0);
+                // Exponente de Phong equivalente; nunca baja de 1 para que el brillo
+                // siga dependiendo del ángulo en las superficies más ásperas
+                intersect.material.specular = (2.0 / (roughness * roughness) - 2.0).max(1.0);
+            }
+            Channel::Emission(color) => {
+                intersect.material.emission = intersect.material.emission + color * self.pattern.value(&point);
+            }
+            Channel::Bump(strength) => {
+                let gradient = self.pattern.gradient(&point);
+                let normal = intersect.shading_normal;
+                let tangential = gradient - normal * gradient.dot(&normal);
+                intersect.shading_normal = (normal - tangential * strength).normalize();
+            }
+        }
+    }
+}
+
+impl Pattern {
+    pub fn value(&self, point: &Vec3) -> f32 {
+        match *self {
+            Pattern::Noise { scale } => noise(&(point * scale)) * 0.5 + 0.5,
+            Pattern::Fbm { scale, octaves } => (fbm(&(point * scale), octaves) * 0.5 + 0.5).clamp(0.0, 1.0),
+            Pattern::Turbulence { scale, octaves } => turbulence(&(point * scale), octaves).clamp(0.0, 1.0),
+            Pattern::Checker { scale } => {
+                let p = point * scale;
+                let sum = p.x.floor() as i64 + p.y.floor() as i64 + p.z.floor() as i64;
+                sum.rem_euclid(2) as f32
+            }
+            Pattern::Wood { scale, rings } => {
+                let p = point * scale;
+                let radius = (p.x * p.x + p.z * p.z).sqrt() * rings + fbm(&p, 3) * 1.5;
+                radius - radius.floor()
+            }
+            Pattern::Marble { scale, veins } => {
+                let p = point * scale;
+                0.5 + 0.5 * (p.x * veins + turbulence(&p, 5) * 6.0).sin()
+            }
+            Pattern::Voronoi { scale } => voronoi(&(point * scale)).min(1.0),
+        }
+    }
+
+    // Gradiente por diferencias finitas, usado para el relieve
+    pub fn gradient(&self, point: &Vec3) -> Vec3 {
+        let epsilon = 1e-3;
+        let center = self.value(point);
+        Vec3::new(
+            self.value(&(point + Vec3::new(epsilon, 0.0, 0.0))) - center,
+            self.value(&(point + Vec3::new(0.0, epsilon, 0.0))) - center,
+            self.value(&(point + Vec3::new(0.0, 0.0, epsilon))) - center,
+        ) / epsilon
+    }
+}
+
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8DA6_B343)
+        ^ (y as u32).wrapping_mul(0xD816_3841)
+        ^ (z as u32).wrapping_mul(0xCB1A_B31F);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0x5BD1_E995);
+    h ^ (h >> 15)
+}
+
+fn gradient_dot(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    // Las 12 direcciones de gradiente del ruido de Perlin mejorado
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 { y } else if h == 12 || h == 14 { x } else { z };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+// Ruido de Perlin en [-1, 1]
+pub fn noise(point: &Vec3) -> f32 {
+    let (xi, yi, zi) = (point.x.floor() as i32, point.y.floor() as i32, point.z.floor() as i32);
+    let (x, y, z) = (point.x - xi as f32, point.y - yi as f32, point.z - zi as f32);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        gradient_dot(hash(xi + dx, yi + dy, zi + dz), x - dx as f32, y - dy as f32, z - dz as f32)
+    };
+
+    lerp(
+        lerp(
+            lerp(corner(0, 0, 0), corner(1, 0, 0), u),
+            lerp(corner(0, 1, 0), corner(1, 1, 0), u),
+            v,
+        ),
+        lerp(
+            lerp(corner(0, 0, 1), corner(1, 0, 1), u),
+            lerp(corner(0, 1, 1), corner(1, 1, 1), u),
+            v,
+        ),
+        w,
+    )
+}
+
+// Movimiento browniano fraccional: octavas de ruido con amplitud decreciente
+pub fn fbm(point: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += noise(&(point * frequency)) * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+pub fn turbulence(point: &Vec3, octaves: u32) -> f32 {
+    let mut sum = 0.0;
+    let mut amplitude = 0.5;
+    let mut frequency = 1.0;
+    for _ in 0..octaves {
+        sum += noise(&(point * frequency)).abs() * amplitude;
+        amplitude *= 0.5;
+        frequency *= 2.0;
+    }
+    sum
+}
+
+pub fn voronoi(point: &Vec3) -> f32 {
+    let cell = point.map(f32::floor);
+    let mut nearest = f32::INFINITY;
+
+    for dz in -1..=1 {
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy, cz) = (cell.x as i32 + dx, cell.y as i32 + dy, cell.z as i32 + dz);
+                let h = hash(cx, cy, cz);
+                let feature = Vec3::new(
+                    cx as f32 + (h & 0xFF) as f32 / 255.0,
+                    cy as f32 + ((h >> 8) & 0xFF) as f32 / 255.0,
+                    cz as f32 + ((h >> 16) & 0xFF) as f32 / 255.0,
+                );
+                nearest = nearest.min((feature - point).magnitude());
+            }
+        }
+    }
+
+    nearest
+}
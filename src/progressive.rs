@@ -0,0 +1,40 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+
+// Refinamiento progresivo: mientras la vista no cambia, cada cuadro agrega
+// una muestra por píxel al acumulador del framebuffer y se muestra el promedio
+pub struct Progressive {
+    // Tamaño en píxeles de los bloques de la vista previa en movimiento
+    pub preview_block: usize,
+    // Al llegar a esta cantidad de muestras se deja de renderizar
+    pub max_samples: u32,
+    camera: Option<Camera>,
+    lights: Vec<Light>,
+}
+
+impl Progressive {
+    pub fn new(preview_block: usize, max_samples: u32) -> Self {
+        Self {
+            preview_block,
+            max_samples,
+            camera: None,
+            lights: Vec::new(),
+        }
+    }
+
+    // Compara con el cuadro anterior y devuelve verdadero si la cámara o
+    // alguna luz cambiaron
+    pub fn track(&mut self, camera: &Camera, lights: &[Light]) -> bool {
+        let changed = self.camera.as_ref() != Some(camera) || self.lights != lights;
+        if changed {
+            self.camera = Some(camera.clone());
+            self.lights = lights.to_vec();
+        }
+        changed
+    }
+
+    pub fn is_converged(&self, framebuffer: &Framebuffer) -> bool {
+        framebuffer.accumulated_samples() >= self.max_samples
+    }
+}
@@ -0,0 +1,77 @@
+use std::f32::consts::PI;
+
+// Generador xorshift sencillo para no depender de crates externos.
+#[derive(Debug, Clone, Copy)]
+pub struct Rng {
+    state: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Self {
+            state: if seed == 0 { 0x9E37_79B9 } else { seed },
+        }
+    }
+
+    pub fn for_pixel(x: usize, y: usize, frame: u32) -> Self {
+        let seed = (x as u32).wrapping_mul(73_856_093)
+            ^ (y as u32).wrapping_mul(19_349_663)
+            ^ frame.wrapping_mul(83_492_791);
+        Self::new(seed)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    // Valor uniforme en [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    // Punto uniforme en el disco unitario (mapeo concéntrico)
+    pub fn in_unit_disk(&mut self) -> (f32, f32) {
+        let a = 2.0 * self.next_f32() - 1.0;
+        let b = 2.0 * self.next_f32() - 1.0;
+
+        if a == 0.0 && b == 0.0 {
+            return (0.0, 0.0);
+        }
+
+        let (r, theta) = if a.abs() > b.abs() {
+            (a, (PI / 4.0) * (b / a))
+        } else {
+            (b, PI / 2.0 - (PI / 4.0) * (a / b))
+        };
+
+        (r * theta.cos(), r * theta.sin())
+    }
+
+    // Punto uniforme dentro de un polígono regular inscrito en el círculo unitario
+    pub fn in_regular_polygon(&mut self, sides: u32, rotation: f32) -> (f32, f32) {
+        let sides = sides.max(3);
+        let wedge = 2.0 * PI / sides as f32;
+        let index = (self.next_f32() * sides as f32) as u32 % sides;
+
+        let a0 = rotation + wedge * index as f32;
+        let a1 = a0 + wedge;
+
+        // Muestreo uniforme de un triángulo (centro, vértice a0, vértice a1)
+        let mut u = self.next_f32();
+        let mut v = self.next_f32();
+        if u + v > 1.0 {
+            u = 1.0 - u;
+            v = 1.0 - v;
+        }
+
+        (
+            u * a0.cos() + v * a1.cos(),
+            u * a0.sin() + v * a1.sin(),
+        )
+    }
+}
@@ -0,0 +1,84 @@
+use nalgebra_glm::Vec3;
+use crate::ray_intersect::Intersect;
+use crate::texture::{Filter, Footprint};
+
+// Diferenciales de rayo: cuánto cambian el origen y la dirección al pasar
+// al píxel vecino en x y en y. Permiten estimar qué área de la textura cubre
+// cada píxel, incluso después de reflexiones y refracciones.
+#[derive(Debug, Clone, Copy)]
+pub struct RayDifferential {
+    pub origin_dx: Vec3,
+    pub origin_dy: Vec3,
+    pub direction_dx: Vec3,
+    pub direction_dy: Vec3,
+}
+
+pub struct Ray {
+    pub origin: Vec3,
+    pub direction: Vec3,
+    pub differential: RayDifferential,
+}
+
+impl RayDifferential {
+    // Rayo primario: todos parten del ojo y solo cambia la dirección
+    pub fn primary(direction_dx: Vec3, direction_dy: Vec3) -> Self {
+        Self {
+            origin_dx: Vec3::zeros(),
+            origin_dy: Vec3::zeros(),
+            direction_dx,
+            direction_dy,
+        }
+    }
+}
+
+impl Ray {
+    pub fn new(origin: Vec3, direction: Vec3, differential: RayDifferential) -> Self {
+        Self { origin, direction, differential }
+    }
+
+    // Desplazamiento del punto de impacto en x e y, proyectado sobre el plano
+    // tangente de la superficie
+    pub fn point_differentials(&self, intersect: &Intersect) -> (Vec3, Vec3) {
+        let cos_theta = self.direction.dot(&intersect.normal);
+        let transfer = |origin: &Vec3, direction: &Vec3| {
+            let offset = origin + direction * intersect.distance;
+            if cos_theta.abs() < 1e-6 {
+                return offset;
+            }
+            offset - self.direction * (offset.dot(&intersect.normal) / cos_theta)
+        };
+
+        (
+            transfer(&self.differential.origin_dx, &self.differential.direction_dx),
+            transfer(&self.differential.origin_dy, &self.differential.direction_dy),
+        )
+    }
+
+    pub fn footprint(&self, intersect: &Intersect, filter: Filter) -> Footprint {
+        let (point_dx, point_dy) = self.point_differentials(intersect);
+        Footprint {
+            duv_dx: intersect.uv_change(&point_dx),
+            duv_dy: intersect.uv_change(&point_dy),
+            filter,
+        }
+    }
+
+    // Rayo secundario que sale del punto de impacto con la dirección que da
+    // `bend` (reflexión o refracción). La derivada de la nueva dirección se
+    // aproxima desviando también las direcciones de los píxeles vecinos.
+    pub fn bent(&self, intersect: &Intersect, origin: Vec3, direction: Vec3, bend: impl Fn(&Vec3) -> Vec3) -> Ray {
+        let (point_dx, point_dy) = self.point_differentials(intersect);
+        let neighbor = |direction_d: &Vec3| bend(&(self.direction + direction_d).normalize()) - direction;
+
+        Ray::new(
+            origin,
+            direction,
+            RayDifferential {
+                origin_dx: point_dx,
+                origin_dy: point_dy,
+                direction_dx: neighbor(&self.differential.direction_dx),
+                direction_dy: neighbor(&self.differential.direction_dy),
+            },
+        )
+    }
+}
@@ -1,13 +1,32 @@
-use nalgebra_glm::Vec3;
+use nalgebra_glm::{Vec2, Vec3};
+use crate::block::Face;
+use crate::environment::color_to_vec3;
 use crate::material::Material;
 
+// Separación del punto de impacto para que los rayos secundarios no choquen
+// con la misma superficie
+const ORIGIN_BIAS: f32 = 1e-4;
+
 #[derive(Debug, Clone, Copy)]
 pub struct Intersect {
     pub point: Vec3,
+    // Normal geométrica: decide de qué lado se desplazan los rayos secundarios
+    // y si el rayo entra o sale del objeto
     pub normal: Vec3,
+    // Normal para iluminar, inclinada por los mapas de normales y de relieve
+    pub shading_normal: Vec3,
     pub distance: f32,
     pub is_intersecting: bool,
     pub material: Material,
+    // Coordenadas de textura sobre la superficie
+    pub uv: Vec2,
+    // Direcciones en que crecen u y v; junto con la normal forman la base tangente
+    pub tangent: Vec3,
+    pub bitangent: Vec3,
+    // Distancia en el mundo que recorre una unidad de u y de v
+    pub uv_scale: Vec2,
+    // Cara del bloque que recibió el impacto, si el objeto es un bloque
+    pub face: Option<Face>,
 }
 
 impl Intersect {
@@ -15,23 +34,167 @@ impl Intersect {
         Self {
             point,
             normal,
+            shading_normal: normal,
             distance,
             is_intersecting: true,
             material,
+            uv: Vec2::zeros(),
+            tangent: Vec3::zeros(),
+            bitangent: Vec3::zeros(),
+            uv_scale: Vec2::new(1.0, 1.0),
+            face: None,
+        }
+    }
+
+    pub fn with_uv(mut self, uv: Vec2) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    pub fn with_tangent_frame(mut self, tangent: Vec3, bitangent: Vec3) -> Self {
+        self.tangent = tangent;
+        self.bitangent = bitangent;
+        self
+    }
+
+    pub fn with_uv_scale(mut self, uv_scale: Vec2) -> Self {
+        self.uv_scale = uv_scale;
+        self
+    }
+
+    // Cambio de las UV ante un desplazamiento del punto sobre la superficie
+    pub fn uv_change(&self, offset: &Vec3) -> Vec2 {
+        Vec2::new(
+            offset.dot(&self.tangent) / self.uv_scale.x,
+            offset.dot(&self.bitangent) / self.uv_scale.y,
+        )
+    }
+
+    // Origen de un rayo secundario, desplazado hacia el lado de la superficie
+    // por el que sale la dirección
+    pub fn offset_origin(&self, direction: &Vec3) -> Vec3 {
+        let offset = self.normal * ORIGIN_BIAS;
+        if direction.dot(&self.normal) < 0.0 {
+            self.point - offset
+        } else {
+            self.point + offset
         }
     }
 
+    // Invierte las dos normales, p. ej. para ver una cara desde adentro
+    pub fn flip_normal(&mut self) {
+        self.normal = -self.normal;
+        self.shading_normal = -self.shading_normal;
+    }
+
+    pub fn with_face(mut self, face: Face) -> Self {
+        self.face = Some(face);
+        self
+    }
+
     pub fn empty() -> Self {
         Self {
             point: Vec3::zeros(),
             normal: Vec3::zeros(),
+            shading_normal: Vec3::zeros(),
             distance: 0.0,
             is_intersecting: false,
             material: Material::black(),
+            uv: Vec2::zeros(),
+            tangent: Vec3::zeros(),
+            bitangent: Vec3::zeros(),
+            uv_scale: Vec2::new(1.0, 1.0),
+            face: None,
         }
     }
 }
 
+// Tramo del rayo que queda dentro de un objeto, de la cara de entrada a la de salida
+#[derive(Debug, Clone, Copy)]
+pub struct Interval {
+    pub enter: Intersect,
+    pub exit: Intersect,
+}
+
+// Une listas de tramos que se solapan y las deja ordenadas por distancia
+pub fn merge_intervals(mut intervals: Vec<Interval>) -> Vec<Interval> {
+    intervals.sort_by(|a, b| a.enter.distance.total_cmp(&b.enter.distance));
+
+    let mut merged: Vec<Interval> = Vec::with_capacity(intervals.len());
+    for interval in intervals {
+        match merged.last_mut() {
+            Some(last) if interval.enter.distance <= last.exit.distance => {
+                if interval.exit.distance > last.exit.distance {
+                    last.exit = interval.exit;
+                }
+            }
+            _ => merged.push(interval),
+        }
+    }
+    merged
+}
+
 pub trait RayIntersect {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+
+    // Intersección en un instante dado del obturador; los objetos estáticos
+    // ignoran el tiempo.
+    fn ray_intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, _time: f32) -> Intersect {
+        self.ray_intersect(ray_origin, ray_direction)
+    }
+
+    // Todos los tramos en que el rayo está dentro del objeto, ordenados por
+    // distancia. Lo necesitan las operaciones CSG.
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Interval>;
+
+    // Descripción corta del objeto para el inspector
+    fn name(&self) -> String {
+        String::from("Objeto")
+    }
+}
+
+// Fracción de la luz (por canal) que recorre el tramo hasta `distance` sin
+// bloquearse. Los objetos transparentes dejan pasar luz teñida según el grosor
+// atravesado y los efectos se acumulan.
+pub fn shadow_transmittance(
+    origin: &Vec3,
+    direction: &Vec3,
+    distance: f32,
+    objects: &[Box<dyn RayIntersect>],
+    time: f32,
+) -> Vec3 {
+    let mut transmittance = Vec3::new(1.0, 1.0, 1.0);
+
+    for object in objects.iter() {
+        let hit = object.ray_intersect_at(origin, direction, time);
+        if !hit.is_intersecting || hit.distance <= 0.0 || hit.distance >= distance {
+            continue;
+        }
+
+        let material = hit.material;
+        if material.albedo[3] > 0.0 {
+            // Grosor atravesado dentro del objeto, limitado al tramo hasta la luz
+            let thickness: f32 = object
+                .ray_intervals(origin, direction, time)
+                .iter()
+                .map(|interval| (interval.exit.distance.min(distance) - interval.enter.distance.max(0.0)).max(0.0))
+                .sum();
+            // Sin absorción propia, el vidrio tiñe la sombra con su color difuso
+            let tint = if material.absorption == Vec3::zeros() {
+                color_to_vec3(material.diffuse)
+            } else {
+                (-material.absorption * thickness).map(f32::exp)
+            };
+            transmittance = transmittance.component_mul(&tint) * material.albedo[3].min(1.0);
+        } else {
+            let distance_ratio = hit.distance / distance;
+            transmittance *= distance_ratio.powf(2.0).min(1.0);
+        }
+
+        if transmittance.max() < 1e-3 {
+            return Vec3::zeros();
+        }
+    }
+
+    transmittance
 }
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+// Ajusta la resolución interna para acercarse al tiempo por cuadro deseado:
+// baja la escala si los cuadros tardan demasiado y la sube si sobra tiempo
+pub struct DynamicResolution {
+    pub target_frame_time: f32,
+    pub min_scale: f32,
+    pub max_scale: f32,
+    scale: f32,
+    // Promedio exponencial del tiempo por cuadro, en segundos
+    frame_time: f32,
+    cooldown: u32,
+}
+
+// La escala cambia en pasos fijos para no cambiar de tamaño en cada cuadro
+const SCALE_STEP: f32 = 0.05;
+const COOLDOWN_FRAMES: u32 = 5;
+
+impl DynamicResolution {
+    pub fn new(target_fps: f32, min_scale: f32, max_scale: f32) -> Self {
+        let target_frame_time = 1.0 / target_fps;
+        Self {
+            target_frame_time,
+            min_scale,
+            max_scale,
+            scale: max_scale,
+            frame_time: target_frame_time,
+            cooldown: 0,
+        }
+    }
+
+    pub fn frame_time(&self) -> f32 {
+        self.frame_time
+    }
+
+    pub fn fps(&self) -> f32 {
+        1.0 / self.frame_time.max(1e-6)
+    }
+
+    // Solo mide el cuadro, sin ajustar la escala
+    pub fn record(&mut self, elapsed: Duration) {
+        self.frame_time = self.frame_time * 0.8 + elapsed.as_secs_f32() * 0.2;
+    }
+
+    // Mide el cuadro y ajusta la escala
+    pub fn update(&mut self, elapsed: Duration) {
+        self.record(elapsed);
+        if self.cooldown > 0 {
+            self.cooldown -= 1;
+            return;
+        }
+
+        let scale = if self.frame_time > self.target_frame_time * 1.2 {
+            self.scale - SCALE_STEP
+        } else if self.frame_time < self.target_frame_time * 0.7 {
+            self.scale + SCALE_STEP
+        } else {
+            self.scale
+        }
+        .clamp(self.min_scale, self.max_scale);
+
+        if (scale - self.scale).abs() > 1e-4 {
+            self.scale = scale;
+            // Deja que el promedio se asiente con el nuevo tamaño antes de volver a ajustar
+            self.cooldown = COOLDOWN_FRAMES;
+        }
+    }
+
+    pub fn render_size(&self, width: usize, height: usize) -> (usize, usize) {
+        (
+            ((width as f32 * self.scale).round() as usize).max(1),
+            ((height as f32 * self.scale).round() as usize).max(1),
+        )
+    }
+}
@@ -0,0 +1,39 @@
+use crate::ambient_occlusion::AmbientOcclusion;
+use crate::denoise::Denoiser;
+use crate::environment::Environment;
+use crate::image_lighting::ImageBasedLighting;
+use crate::light::Light;
+use crate::ray_intersect::RayIntersect;
+use crate::texture::Filter;
+use crate::volume::Media;
+
+pub struct Scene {
+    pub objects: Vec<Box<dyn RayIntersect>>,
+    pub lights: Vec<Light>,
+    pub environment: Environment,
+    pub image_lighting: Option<ImageBasedLighting>,
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    pub media: Media,
+    pub texture_filter: Filter,
+    pub denoiser: Option<Denoiser>,
+}
+
+impl Scene {
+    pub fn new(objects: Vec<Box<dyn RayIntersect>>, lights: Vec<Light>, environment: Environment) -> Self {
+        Self {
+            objects,
+            lights,
+            environment,
+            image_lighting: None,
+            ambient_occlusion: None,
+            media: Media::empty(),
+            texture_filter: Filter::Trilinear,
+            denoiser: None,
+        }
+    }
+
+    // Activa la iluminación basada en imagen a partir del entorno actual
+    pub fn enable_image_lighting(&mut self, samples: u32, intensity: f32) {
+        self.image_lighting = Some(ImageBasedLighting::new(&self.environment, 128, 64, samples, intensity));
+    }
+}
@@ -0,0 +1,165 @@
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+
+// Imagen en punto flotante (RGBA lineal) que se puede muestrear con
+// coordenadas UV. Sirve tanto para texturas como para mapas de entorno.
+#[derive(Debug)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+    texels: Vec<Vec4>,
+    // Pirámide de versiones reducidas a la mitad, hasta 1x1
+    mips: Vec<Texture>,
+}
+
+// Cómo se filtra la textura cuando un píxel cubre varios texels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    Bilinear,
+    // Mezcla entre los dos niveles de mipmap más cercanos a la huella
+    Trilinear,
+    // Varias muestras trilineales a lo largo del eje mayor de la huella
+    Anisotropic(u32),
+}
+
+// Huella del píxel en la textura: cuánto cambian las UV al pasar al píxel
+// vecino en x y en y
+#[derive(Debug, Clone, Copy)]
+pub struct Footprint {
+    pub duv_dx: Vec2,
+    pub duv_dy: Vec2,
+    pub filter: Filter,
+}
+
+impl Texture {
+    pub fn load(path: &str) -> image::ImageResult<Self> {
+        let image = image::open(path)?.to_rgba32f();
+        let (width, height) = image.dimensions();
+        let texels = image
+            .pixels()
+            .map(|pixel| Vec4::new(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect();
+
+        Ok(Self::with_mipmaps(width as usize, height as usize, texels))
+    }
+
+    pub fn from_fn(width: usize, height: usize, f: impl Fn(usize, usize) -> Vec4) -> Self {
+        let mut texels = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                texels.push(f(x, y));
+            }
+        }
+        Self::with_mipmaps(width, height, texels)
+    }
+
+    // Genera la pirámide promediando bloques de 2x2 texels en cada nivel
+    fn with_mipmaps(width: usize, height: usize, texels: Vec<Vec4>) -> Self {
+        let mut texture = Self { width, height, texels, mips: Vec::new() };
+        let mut mips: Vec<Texture> = Vec::new();
+
+        while mips.last().map_or(texture.width.max(texture.height), |mip| mip.width.max(mip.height)) > 1 {
+            let previous = mips.last().unwrap_or(&texture);
+            let (width, height) = ((previous.width / 2).max(1), (previous.height / 2).max(1));
+            let mut texels = Vec::with_capacity(width * height);
+            for y in 0..height as i64 {
+                for x in 0..width as i64 {
+                    let sum = previous.texel(2 * x, 2 * y)
+                        + previous.texel(2 * x + 1, 2 * y)
+                        + previous.texel(2 * x, 2 * y + 1)
+                        + previous.texel(2 * x + 1, 2 * y + 1);
+                    texels.push(sum * 0.25);
+                }
+            }
+            mips.push(Self { width, height, texels, mips: Vec::new() });
+        }
+
+        texture.mips = mips;
+        texture
+    }
+
+    fn level(&self, index: usize) -> &Texture {
+        if index == 0 { self } else { &self.mips[(index - 1).min(self.mips.len() - 1)] }
+    }
+
+    // Convierte un mapa de alturas en escala de grises en un mapa de normales
+    pub fn to_normal_map(&self, strength: f32) -> Self {
+        let height = |x: i64, y: i64| {
+            let texel = self.texel(x, y);
+            (texel.x + texel.y + texel.z) / 3.0
+        };
+
+        Self::from_fn(self.width, self.height, |x, y| {
+            let (x, y) = (x as i64, y as i64);
+            let dx = (height(x + 1, y) - height(x - 1, y)) * 0.5 * strength;
+            let dy = (height(x, y + 1) - height(x, y - 1)) * 0.5 * strength;
+            let normal = Vec3::new(-dx, -dy, 1.0).normalize();
+            Vec4::new(normal.x * 0.5 + 0.5, normal.y * 0.5 + 0.5, normal.z * 0.5 + 0.5, 1.0)
+        })
+    }
+
+    // Texel con coordenadas que se repiten fuera de los bordes
+    pub fn texel(&self, x: i64, y: i64) -> Vec4 {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.texels[y * self.width + x]
+    }
+
+    // Muestreo bilineal; (0, 0) es la esquina superior izquierda
+    pub fn sample(&self, u: f32, v: f32) -> Vec4 {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+        let x0 = x.floor();
+        let y0 = y.floor();
+        let fx = x - x0;
+        let fy = y - y0;
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0) * (1.0 - fx) + self.texel(x0 + 1, y0) * fx;
+        let bottom = self.texel(x0, y0 + 1) * (1.0 - fx) + self.texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    // Muestreo bilineal interpolado entre los dos niveles alrededor de `lod`
+    fn sample_level(&self, u: f32, v: f32, lod: f32) -> Vec4 {
+        let lod = lod.clamp(0.0, self.mips.len() as f32);
+        let fine = lod.floor();
+        let t = lod - fine;
+        let fine = fine as usize;
+
+        let sample = self.level(fine).sample(u, v);
+        if t == 0.0 {
+            sample
+        } else {
+            sample * (1.0 - t) + self.level(fine + 1).sample(u, v) * t
+        }
+    }
+
+    // Muestreo según la huella del píxel para evitar el parpadeo a distancia
+    pub fn sample_footprint(&self, uv: &Vec2, footprint: &Footprint) -> Vec4 {
+        let size = Vec2::new(self.width as f32, self.height as f32);
+        let axis_x = footprint.duv_dx.component_mul(&size).magnitude();
+        let axis_y = footprint.duv_dy.component_mul(&size).magnitude();
+
+        match footprint.filter {
+            Filter::Bilinear => self.sample(uv.x, uv.y),
+            Filter::Trilinear => self.sample_level(uv.x, uv.y, axis_x.max(axis_y).max(1e-8).log2()),
+            Filter::Anisotropic(max_samples) => {
+                let (major, minor, major_duv) = if axis_x >= axis_y {
+                    (axis_x, axis_y, footprint.duv_dx)
+                } else {
+                    (axis_y, axis_x, footprint.duv_dy)
+                };
+                let ratio = (major / minor.max(1e-8)).min(max_samples.max(1) as f32).max(1.0);
+                let lod = (major / ratio).max(1e-8).log2();
+                let samples = ratio.ceil() as u32;
+
+                let mut sum = Vec4::zeros();
+                for i in 0..samples {
+                    let offset = major_duv * ((i as f32 + 0.5) / samples as f32 - 0.5);
+                    sum += self.sample_level(uv.x + offset.x, uv.y + offset.y, lod);
+                }
+                sum / samples as f32
+            }
+        }
+    }
+}
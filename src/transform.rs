@@ -0,0 +1,144 @@
+use std::rc::Rc;
+use nalgebra_glm::{self as glm, Mat3, Mat4, Vec3, Vec4};
+use crate::ray_intersect::{RayIntersect, Intersect, Interval, merge_intervals};
+
+// Aplica traslación, rotación y escala a cualquier objeto. La geometría se
+// comparte con `Rc`, así que varias instancias pueden apuntar al mismo objeto.
+pub struct Transform {
+    pub object: Rc<dyn RayIntersect>,
+    matrix: Mat4,
+    inverse: Mat4,
+    normal_matrix: Mat3,
+}
+
+impl Transform {
+    pub fn new(object: Rc<dyn RayIntersect>, matrix: Mat4) -> Self {
+        let inverse = glm::inverse(&matrix);
+        let normal_matrix = glm::transpose(&glm::mat4_to_mat3(&inverse));
+
+        Self {
+            object,
+            matrix,
+            inverse,
+            normal_matrix,
+        }
+    }
+
+    pub fn identity(object: Rc<dyn RayIntersect>) -> Self {
+        Self::new(object, Mat4::identity())
+    }
+
+    // Las operaciones se aplican en el orden en que se encadenan (primero la primera)
+    pub fn translate(self, offset: Vec3) -> Self {
+        let matrix = glm::translation(&offset) * self.matrix;
+        Self::new(self.object, matrix)
+    }
+
+    pub fn rotate(self, angle: f32, axis: Vec3) -> Self {
+        let matrix = glm::rotation(angle, &axis) * self.matrix;
+        Self::new(self.object, matrix)
+    }
+
+    pub fn scale(self, factor: Vec3) -> Self {
+        let matrix = glm::scaling(&factor) * self.matrix;
+        Self::new(self.object, matrix)
+    }
+
+    // El rayo se lleva a espacio local sin normalizar la dirección para
+    // que la distancia paramétrica siga siendo válida en espacio mundo.
+    fn to_local(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> (Vec3, Vec3) {
+        let local_origin = (self.inverse * Vec4::new(ray_origin.x, ray_origin.y, ray_origin.z, 1.0)).xyz();
+        let local_direction = (self.inverse * Vec4::new(ray_direction.x, ray_direction.y, ray_direction.z, 0.0)).xyz();
+        (local_origin, local_direction)
+    }
+
+    fn to_world(&self, intersect: &mut Intersect) {
+        let point = intersect.point;
+        intersect.point = (self.matrix * Vec4::new(point.x, point.y, point.z, 1.0)).xyz();
+        intersect.normal = (self.normal_matrix * intersect.normal).normalize();
+        intersect.shading_normal = (self.normal_matrix * intersect.shading_normal).normalize();
+        let tangent = (self.matrix * Vec4::new(intersect.tangent.x, intersect.tangent.y, intersect.tangent.z, 0.0)).xyz();
+        let bitangent = (self.matrix * Vec4::new(intersect.bitangent.x, intersect.bitangent.y, intersect.bitangent.z, 0.0)).xyz();
+        // El escalado estira la textura sobre la superficie
+        intersect.uv_scale.x *= tangent.magnitude();
+        intersect.uv_scale.y *= bitangent.magnitude();
+        intersect.tangent = tangent.normalize();
+        intersect.bitangent = bitangent.normalize();
+    }
+}
+
+impl RayIntersect for Transform {
+    fn name(&self) -> String {
+        format!("{} transformado", self.object.name())
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect {
+        let (local_origin, local_direction) = self.to_local(ray_origin, ray_direction);
+
+        let mut intersect = self.object.ray_intersect_at(&local_origin, &local_direction, time);
+        if intersect.is_intersecting {
+            self.to_world(&mut intersect);
+        }
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Interval> {
+        let (local_origin, local_direction) = self.to_local(ray_origin, ray_direction);
+
+        let mut intervals = self.object.ray_intervals(&local_origin, &local_direction, time);
+        for interval in intervals.iter_mut() {
+            self.to_world(&mut interval.enter);
+            self.to_world(&mut interval.exit);
+        }
+        intervals
+    }
+}
+
+// Conjunto de objetos que se comporta como uno solo; útil como geometría
+// compartida entre instancias.
+pub struct Group {
+    pub objects: Vec<Box<dyn RayIntersect>>,
+}
+
+impl Group {
+    pub fn new(objects: Vec<Box<dyn RayIntersect>>) -> Self {
+        Self { objects }
+    }
+}
+
+impl RayIntersect for Group {
+    fn name(&self) -> String {
+        format!("Grupo de {} objetos", self.objects.len())
+    }
+
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, 0.0)
+    }
+
+    fn ray_intersect_at(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Intersect {
+        let mut intersect = Intersect::empty();
+        let mut zbuffer = f32::INFINITY;
+
+        for object in self.objects.iter() {
+            let i = object.ray_intersect_at(ray_origin, ray_direction, time);
+            if i.is_intersecting && i.distance < zbuffer {
+                zbuffer = i.distance;
+                intersect = i;
+            }
+        }
+
+        intersect
+    }
+
+    fn ray_intervals(&self, ray_origin: &Vec3, ray_direction: &Vec3, time: f32) -> Vec<Interval> {
+        let intervals = self.objects
+            .iter()
+            .flat_map(|object| object.ray_intervals(ray_origin, ray_direction, time))
+            .collect();
+        merge_intervals(intervals)
+    }
+}
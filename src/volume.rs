@@ -0,0 +1,241 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+use crate::color::Color;
+use crate::environment::{color_to_vec3, vec3_to_color};
+use crate::light::Light;
+use crate::random::Rng;
+use crate::ray::Ray;
+use crate::ray_intersect::{shadow_transmittance, RayIntersect};
+use crate::scene::Scene;
+
+// Densidad de un medio: constante (homogéneo) o definida por una función del punto
+#[derive(Clone, Copy)]
+pub enum Density {
+    Constant(f32),
+    Field(fn(&Vec3) -> f32),
+}
+
+impl Density {
+    pub fn at(&self, point: &Vec3) -> f32 {
+        match self {
+            Density::Constant(density) => *density,
+            Density::Field(field) => field(point).max(0.0),
+        }
+    }
+}
+
+// Niebla global que depende solo de la distancia recorrida
+pub struct Fog {
+    pub color: Color,
+    pub density: f32,
+}
+
+// Medio participativo limitado por una caja alineada con los ejes, p. ej. humo
+pub struct Volume {
+    pub min: Vec3,
+    pub max: Vec3,
+    pub density: Density,
+    // Coeficientes por canal (r, g, b) por unidad de distancia
+    pub absorption: Vec3,
+    pub scattering: Vec3,
+}
+
+impl Volume {
+    pub fn contains(&self, point: &Vec3) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    // Distancias de entrada y salida del rayo en la caja, si la atraviesa
+    fn span(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Option<(f32, f32)> {
+        let inv_dir = ray_direction.map(|d| 1.0 / d);
+        let t1 = (self.min - ray_origin).component_mul(&inv_dir);
+        let t2 = (self.max - ray_origin).component_mul(&inv_dir);
+
+        let enter = t1.zip_map(&t2, f32::min).max();
+        let exit = t1.zip_map(&t2, f32::max).min();
+        (enter <= exit && exit > 0.0).then_some((enter, exit))
+    }
+}
+
+// Resultado de atravesar el medio: cuánto sobrevive de lo que hay detrás
+// y cuánta luz se dispersó hacia la cámara
+pub struct MediaSample {
+    pub transmittance: Vec3,
+    pub scattered: Vec3,
+}
+
+impl MediaSample {
+    pub fn apply(&self, surface: Color) -> Color {
+        vec3_to_color(&(color_to_vec3(surface).component_mul(&self.transmittance) + self.scattered))
+    }
+}
+
+pub struct Media {
+    pub fog: Option<Fog>,
+    pub volumes: Vec<Volume>,
+    pub steps: u32,
+    // Pasos por tramo en que se trazan rayos de sombra hacia las luces; en los
+    // demás se reutiliza la última luz calculada
+    pub shadow_steps: u32,
+    pub max_distance: f32,
+    // Parámetro g de Henyey-Greenstein: 0 isotrópico, > 0 dispersa hacia delante
+    pub anisotropy: f32,
+    pub light_shafts: bool,
+}
+
+impl Media {
+    pub fn empty() -> Self {
+        Self {
+            fog: None,
+            volumes: Vec::new(),
+            steps: 16,
+            shadow_steps: 4,
+            max_distance: 10.0,
+            anisotropy: 0.3,
+            light_shafts: true,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fog.is_none() && self.volumes.is_empty()
+    }
+
+    // Coeficientes de extinción y dispersión en un punto
+    fn coefficients(&self, point: &Vec3) -> (Vec3, Vec3) {
+        let mut extinction = Vec3::zeros();
+        let mut scattering = Vec3::zeros();
+
+        if let Some(fog) = &self.fog {
+            extinction += Vec3::repeat(fog.density);
+            scattering += color_to_vec3(fog.color) * fog.density;
+        }
+
+        for volume in self.volumes.iter() {
+            if volume.contains(point) {
+                let density = volume.density.at(point);
+                extinction += (volume.absorption + volume.scattering) * density;
+                scattering += volume.scattering * density;
+            }
+        }
+
+        (extinction, scattering)
+    }
+
+    fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy;
+        let denominator = (1.0 + g * g - 2.0 * g * cos_theta).max(1e-4);
+        (1.0 - g * g) / (4.0 * PI * denominator * denominator.sqrt())
+    }
+
+    // Luz de las fuentes que llega a un punto del medio. Con `shadowed` se
+    // trazan rayos de sombra (rayos de luz volumétricos); sin ellos la luz
+    // llega sin obstáculos, lo que basta para los rayos secundarios
+    fn in_scattered(
+        &self,
+        point: &Vec3,
+        view_direction: &Vec3,
+        objects: &[Box<dyn RayIntersect>],
+        lights: &[Light],
+        time: f32,
+        shadowed: bool,
+    ) -> Vec3 {
+        let mut radiance = Vec3::zeros();
+
+        for light in lights.iter().filter(|light| light.active && light.intensity > 0.0) {
+            let to_light = light.position - point;
+            let light_distance = to_light.magnitude();
+            let light_dir = to_light / light_distance;
+
+            // Los objetos transparentes, como el agua que contiene al punto, dejan pasar la luz
+            let transmittance = if shadowed {
+                shadow_transmittance(point, &light_dir, light_distance, objects, time)
+            } else {
+                Vec3::repeat(1.0)
+            };
+            if transmittance == Vec3::zeros() {
+                continue;
+            }
+
+            let phase = self.phase(view_direction.dot(&light_dir));
+            radiance += color_to_vec3(light.color).component_mul(&transmittance) * light.intensity * phase;
+        }
+
+        radiance
+    }
+
+    // Integra el medio a lo largo del rayo por marcha de rayos: atenúa el
+    // color de la superficie (Beer-Lambert) y suma la luz dispersada. Las
+    // sombras volumétricas solo se calculan para los rayos de cámara (`depth` 0)
+    pub fn integrate(
+        &self,
+        ray: &Ray,
+        distance: Option<f32>,
+        depth: u32,
+        scene: &Scene,
+        time: f32,
+        rng: &mut Rng,
+    ) -> MediaSample {
+        let (ray_origin, ray_direction) = (&ray.origin, &ray.direction);
+        let mut transmittance = Vec3::repeat(1.0);
+        let mut scattered = Vec3::zeros();
+
+        if self.is_empty() {
+            return MediaSample { transmittance, scattered };
+        }
+
+        let end = distance.unwrap_or(self.max_distance).clamp(0.0, self.max_distance);
+
+        // Tramos a recorrer: todo el rayo si hay niebla, o solo el interior de los volúmenes
+        let mut breaks = vec![0.0, end];
+        for volume in self.volumes.iter() {
+            if let Some((enter, exit)) = volume.span(ray_origin, ray_direction) {
+                breaks.push(enter.clamp(0.0, end));
+                breaks.push(exit.clamp(0.0, end));
+            }
+        }
+        breaks.sort_by(f32::total_cmp);
+        breaks.dedup();
+
+        let jitter = rng.next_f32();
+        let shadowed = depth == 0;
+        let shadow_interval = (self.steps / self.shadow_steps.max(1)).max(1);
+
+        for segment in breaks.windows(2) {
+            let (start, stop) = (segment[0], segment[1]);
+            let midpoint = ray_origin + ray_direction * (0.5 * (start + stop));
+            if self.fog.is_none() && self.coefficients(&midpoint).0 == Vec3::zeros() {
+                continue;
+            }
+
+            let step = (stop - start) / self.steps as f32;
+            let mut light = None;
+            for i in 0..self.steps {
+                let t = start + (i as f32 + jitter) * step;
+                let point = ray_origin + ray_direction * t;
+                let (extinction, scattering) = self.coefficients(&point);
+
+                if self.light_shafts && scattering != Vec3::zeros() {
+                    if light.is_none() || i % shadow_interval == 0 {
+                        light = Some(self.in_scattered(&point, ray_direction, &scene.objects, &scene.lights, time, shadowed));
+                    }
+                    if let Some(light) = &light {
+                        scattered += transmittance.component_mul(&scattering).component_mul(light) * step;
+                    }
+                }
+
+                transmittance = transmittance.component_mul(&(-extinction * step).map(f32::exp));
+            }
+        }
+
+        // Sin rayos de luz, la niebla aporta su propio color como luz ambiental
+        if !self.light_shafts {
+            if let Some(fog) = &self.fog {
+                scattered += color_to_vec3(fog.color).component_mul(&(Vec3::repeat(1.0) - transmittance));
+            }
+        }
+
+        MediaSample { transmittance, scattered }
+    }
+}